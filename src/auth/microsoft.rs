@@ -2,7 +2,7 @@ use crate::error::{AuthError, Result};
 use oauth2::{AuthUrl, ClientId, CsrfToken, RedirectUrl, Scope, TokenUrl, basic::BasicClient};
 use tokio::sync::oneshot;
 use tokio::task;
-use tracing::{debug, error, info, trace, warn};
+use tracing::{debug, error, info, trace};
 
 use super::constants::{MS_AUTH_URL, MS_TOKEN_URL, REDIRECT_URI};
 
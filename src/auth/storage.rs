@@ -1,4 +1,5 @@
 use crate::error::{AuthError, Result, ResultExt};
+use crate::fs_utils::{self, FileLock};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use time::{Duration, OffsetDateTime};
@@ -16,6 +17,7 @@ pub struct CachedAuth {
 
 pub struct AuthStorage {
     cache_file_path: PathBuf,
+    lock_file_path: PathBuf,
 }
 
 impl AuthStorage {
@@ -25,8 +27,12 @@ impl AuthStorage {
             .with_context(|| "Failed to create cache directory".to_string())?;
 
         let cache_file_path = cache_dir.join("auth_cache.json");
+        let lock_file_path = cache_dir.join("auth_cache.lock");
 
-        Ok(Self { cache_file_path })
+        Ok(Self {
+            cache_file_path,
+            lock_file_path,
+        })
     }
 
     /// Get platform-specific cache directory
@@ -80,7 +86,8 @@ impl AuthStorage {
         let json = serde_json::to_string_pretty(&cached_auth)
             .with_context(|| "Failed to serialize cached auth".to_string())?;
 
-        fs::write(&self.cache_file_path, json)
+        let _lock = FileLock::exclusive_async(&self.lock_file_path).await?;
+        fs_utils::write_atomic(&self.cache_file_path, json)
             .await
             .with_context(|| "Failed to write auth cache file".to_string())?;
 
@@ -95,6 +102,7 @@ impl AuthStorage {
 
     /// Load authentication result from cache if valid
     pub async fn load_auth(&self) -> Result<Option<AuthResult>> {
+        let lock = FileLock::shared_async(&self.lock_file_path).await?;
         if !self.cache_file_path.exists() {
             debug!("No auth cache file found");
             return Ok(None);
@@ -112,6 +120,8 @@ impl AuthStorage {
         if now >= cached_auth.expires_at {
             warn!("Cached authentication has expired, requiring fresh login");
             // Clean up expired cache
+            drop(lock);
+            let _lock = FileLock::exclusive_async(&self.lock_file_path).await?;
             let _ = fs::remove_file(&self.cache_file_path).await;
             return Ok(None);
         }
@@ -136,6 +146,7 @@ impl AuthStorage {
 
     /// Clear cached authentication
     pub async fn clear_cache(&self) -> Result<()> {
+        let _lock = FileLock::exclusive_async(&self.lock_file_path).await?;
         if self.cache_file_path.exists() {
             fs::remove_file(&self.cache_file_path)
                 .await
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "rustified")]
@@ -42,6 +43,36 @@ pub enum Commands {
         /// Skip file verification (faster launch)
         #[arg(long)]
         skip_verification: bool,
+        /// Start the game in the background and return immediately
+        #[arg(short, long)]
+        detach: bool,
+        /// Least severe game log level shown in the terminal
+        #[arg(long, value_enum, default_value = "info")]
+        game_log_level: GameLogLevelOption,
+        /// Join a server once the game has started (host or host:port)
+        #[arg(long, conflicts_with = "world")]
+        server: Option<String>,
+        /// Open a singleplayer world once the game has started (1.20+)
+        #[arg(long)]
+        world: Option<String>,
+        /// Window width in pixels for this launch
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        width: Option<u32>,
+        /// Window height in pixels for this launch
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        height: Option<u32>,
+        /// Start in fullscreen for this launch
+        #[arg(long, conflicts_with = "windowed")]
+        fullscreen: bool,
+        /// Start windowed for this launch
+        #[arg(long)]
+        windowed: bool,
+        /// Print the resolved launch command (secrets redacted) instead of starting the game
+        #[arg(long, conflicts_with = "detach")]
+        dry_run: bool,
+        /// Write a shell script that starts the game like this launch would, without starting it
+        #[arg(long, value_name = "FILE", conflicts_with = "detach")]
+        export_script: Option<PathBuf>,
     },
     /// Prepare (download) a Minecraft version without launching
     Prepare {
@@ -78,7 +109,32 @@ pub enum AuthCommands {
 #[derive(Subcommand)]
 pub enum InstanceCommands {
     /// List all instances
-    List,
+    List {
+        /// Show only instances with this tag (can be used multiple times; all must match)
+        #[arg(long, action = clap::ArgAction::Append)]
+        tag: Vec<String>,
+        /// Show only instances in this group
+        #[arg(long)]
+        group: Option<String>,
+        /// Show only instances whose Minecraft version starts with this prefix
+        #[arg(long)]
+        version: Option<String>,
+        /// Show only instances using this mod loader
+        #[arg(long, value_enum)]
+        loader: Option<ModLoaderFilter>,
+        /// Filter instances by name (case-insensitive substring match)
+        #[arg(long)]
+        filter: Option<String>,
+        /// Sort order for instances
+        #[arg(long, value_enum, default_value = "name")]
+        sort: InstanceSortOrder,
+        /// Output format
+        #[arg(long, value_enum, default_value = "list")]
+        format: OutputFormat,
+        /// Print instances as JSON (shorthand for --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+    },
     /// Create a new instance
     Create {
         /// Instance name
@@ -88,6 +144,30 @@ pub enum InstanceCommands {
         /// Instance description
         #[arg(short, long)]
         description: Option<String>,
+        /// Group to place the instance in
+        #[arg(short, long)]
+        group: Option<String>,
+        /// Tag to attach to the instance (can be used multiple times)
+        #[arg(short, long, action = clap::ArgAction::Append)]
+        tag: Vec<String>,
+    },
+    /// Add or remove instance tags
+    Tag {
+        /// Instance name
+        name: String,
+        /// Tag to add (can be used multiple times)
+        #[arg(long, action = clap::ArgAction::Append)]
+        add: Vec<String>,
+        /// Tag to remove (can be used multiple times)
+        #[arg(long, action = clap::ArgAction::Append)]
+        remove: Vec<String>,
+    },
+    /// Set or clear the group of an instance
+    Group {
+        /// Instance name
+        name: String,
+        /// Group name (omit to remove the instance from its group)
+        group: Option<String>,
     },
     /// Delete an instance
     Delete {
@@ -99,24 +179,156 @@ pub enum InstanceCommands {
         /// Instance name
         name: String,
     },
+    /// Show recent game sessions of an instance
+    History {
+        /// Instance name
+        name: String,
+        /// Maximum number of sessions to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
     /// Set instance memory allocation
+    #[command(group(ArgGroup::new("memory_choice").required(true).multiple(true).args(["memory", "min", "reset"])))]
     Memory {
         /// Instance name
         name: String,
-        /// Memory in MB
-        memory: u32,
+        /// Maximum heap in MB, with a unit (512M, 4G) or as a share of system memory (50%)
+        memory: Option<String>,
+        /// Initial heap, in the same format
+        #[arg(long, value_name = "SIZE")]
+        min: Option<String>,
+        /// Go back to the default heap of 1G initial and 2G maximum
+        #[arg(long, conflicts_with_all = ["memory", "min"])]
+        reset: bool,
+    },
+    /// Pin the Java runtime used by an instance
+    #[command(group(ArgGroup::new("java_choice").required(true).args(["path", "version", "auto"])))]
+    Java {
+        /// Instance name
+        name: String,
+        /// Path to the java executable to use
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Java major version to use (e.g. 17)
+        #[arg(long)]
+        version: Option<u32>,
+        /// Remove any override and pick Java automatically
+        #[arg(long)]
+        auto: bool,
+    },
+    /// Set the server or world an instance joins when launched
+    #[command(group(ArgGroup::new("quick_play_choice").required(true).args(["server", "world", "clear"])))]
+    QuickPlay {
+        /// Instance name
+        name: String,
+        /// Server to join (host or host:port)
+        #[arg(long)]
+        server: Option<String>,
+        /// Singleplayer world to open (1.20+)
+        #[arg(long)]
+        world: Option<String>,
+        /// Start at the title screen again
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Set the game window size and mode of an instance
+    #[command(group(ArgGroup::new("window_choice").required(true).multiple(true).args(["width", "height", "fullscreen", "windowed", "reset"])))]
+    Window {
+        /// Instance name
+        name: String,
+        /// Window width in pixels
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        width: Option<u32>,
+        /// Window height in pixels
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        height: Option<u32>,
+        /// Start in fullscreen
+        #[arg(long, conflicts_with = "windowed")]
+        fullscreen: bool,
+        /// Start windowed
+        #[arg(long)]
+        windowed: bool,
+        /// Go back to the game's default window
+        #[arg(long, conflicts_with_all = ["width", "height", "fullscreen", "windowed"])]
+        reset: bool,
+    },
+    /// Set commands run before and after the game, and a wrapper it is started through
+    #[command(group(ArgGroup::new("hooks_choice").required(true).multiple(true).args(["pre_launch", "post_exit", "wrapper", "clear"])))]
+    Hooks {
+        /// Instance name
+        name: String,
+        /// Shell command run before the game starts; the launch is aborted if it fails ("" removes it)
+        #[arg(long, value_name = "COMMAND")]
+        pre_launch: Option<String>,
+        /// Shell command run after the game exits ("" removes it)
+        #[arg(long, value_name = "COMMAND")]
+        post_exit: Option<String>,
+        /// Command the game is started through, e.g. gamemoderun ("" removes it)
+        #[arg(long, value_name = "COMMAND")]
+        wrapper: Option<String>,
+        /// Remove all hooks and the wrapper
+        #[arg(long, conflicts_with_all = ["pre_launch", "post_exit", "wrapper"])]
+        clear: bool,
+    },
+    /// Set or remove environment variables of the game
+    #[command(group(ArgGroup::new("env_choice").required(true).multiple(true).args(["set", "unset", "reset", "sanitize", "inherit"])))]
+    Env {
+        /// Instance name
+        name: String,
+        /// Set a variable for the game (NAME=VALUE, repeatable)
+        #[arg(long, value_name = "NAME=VALUE")]
+        set: Vec<String>,
+        /// Start the game without a variable (repeatable)
+        #[arg(long, value_name = "NAME")]
+        unset: Vec<String>,
+        /// Drop a previous --set or --unset so the variable is inherited again (repeatable)
+        #[arg(long, value_name = "NAME")]
+        reset: Vec<String>,
+        /// Remove variables the JVM reads options from, such as `_JAVA_OPTIONS`
+        #[arg(long, conflicts_with = "inherit")]
+        sanitize: bool,
+        /// Pass `_JAVA_OPTIONS` and similar variables through to the game again
+        #[arg(long)]
+        inherit: bool,
+    },
+    /// Select the garbage collector flag preset of an instance
+    Preset {
+        /// Instance name
+        name: String,
+        /// Preset to use
+        #[arg(value_enum)]
+        preset: JvmPresetOption,
     },
 }
 
 #[derive(Subcommand)]
 pub enum JavaCommands {
     /// List available Java installations
-    List,
+    List {
+        /// Ignore the discovery cache and probe every installation again
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Register a Java installation in a non-standard location
+    Add {
+        /// Path to a java executable or a Java home directory
+        path: PathBuf,
+    },
+    /// Unregister a Java installation added with `java add`
+    Remove {
+        /// Path that was registered
+        path: PathBuf,
+    },
     /// Show recommended Java version for a Minecraft version
     Recommend {
         /// Minecraft version
         version: String,
     },
+    /// Download the Java runtime a Minecraft version needs from Mojang
+    Install {
+        /// Minecraft version
+        version: String,
+    },
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -140,3 +352,92 @@ pub enum SortOrder {
     /// Alphabetical order
     Alphabetical,
 }
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum ModLoaderFilter {
+    /// Unmodded instances
+    Vanilla,
+    /// Forge instances
+    Forge,
+    /// Fabric instances
+    Fabric,
+    /// Quilt instances
+    Quilt,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum InstanceSortOrder {
+    /// Alphabetical order by name (default)
+    Name,
+    /// Most recently used first
+    LastUsed,
+    /// Newest instances first
+    Created,
+    /// Most played first
+    Playtime,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum OutputFormat {
+    /// One line per entry (default)
+    List,
+    /// Aligned table
+    Table,
+    /// Machine-readable JSON on stdout
+    Json,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum JvmPresetOption {
+    /// G1 tuned like the official launcher (default)
+    Default,
+    /// Aikar's G1 flags
+    Aikar,
+    /// Z Garbage Collector (Java 11+)
+    Zgc,
+    /// Shenandoah (`OpenJDK` 12+)
+    Shenandoah,
+    /// No GC flags
+    Minimal,
+}
+
+impl From<JvmPresetOption> for crate::launcher::jvm::JvmPreset {
+    fn from(option: JvmPresetOption) -> Self {
+        match option {
+            JvmPresetOption::Default => Self::Default,
+            JvmPresetOption::Aikar => Self::Aikar,
+            JvmPresetOption::Zgc => Self::Zgc,
+            JvmPresetOption::Shenandoah => Self::Shenandoah,
+            JvmPresetOption::Minimal => Self::Minimal,
+        }
+    }
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum GameLogLevelOption {
+    /// Everything the game logs
+    Trace,
+    /// Debug messages and above
+    Debug,
+    /// Informational messages and above (default)
+    Info,
+    /// Warnings and errors
+    Warn,
+    /// Errors only
+    Error,
+    /// Fatal errors only
+    Fatal,
+}
+
+impl From<GameLogLevelOption> for crate::launcher::game_log::GameLogLevel {
+    fn from(level: GameLogLevelOption) -> Self {
+        match level {
+            GameLogLevelOption::Trace => Self::Trace,
+            GameLogLevelOption::Debug => Self::Debug,
+            GameLogLevelOption::Info => Self::Info,
+            GameLogLevelOption::Warn => Self::Warn,
+            GameLogLevelOption::Error => Self::Error,
+            GameLogLevelOption::Fatal => Self::Fatal,
+        }
+    }
+}
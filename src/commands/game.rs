@@ -1,6 +1,7 @@
 use crate::cli::{SortOrder, VersionTypeFilter};
 use crate::launcher;
-use tracing::{error, info};
+use std::path::Path;
+use tracing::{error, info, warn};
 
 /// Options for listing Minecraft versions
 #[derive(Debug)]
@@ -183,6 +184,12 @@ pub async fn prepare_game(
         .download_assets(&version_info, &launcher.minecraft_dir)
         .await?;
 
+    // Download the log4j config
+    launcher
+        .file_manager
+        .download_log_config(&version_info, &launcher.minecraft_dir)
+        .await?;
+
     info!("✓ Minecraft {resolved_version} prepared successfully");
     Ok(())
 }
@@ -197,19 +204,20 @@ pub async fn launch_game(
     launcher: &launcher::Launcher,
     instance_name: &str,
     _skip_verification: bool,
+    options: &launcher::LaunchOptions,
+    export_script: Option<&Path>,
 ) -> crate::error::Result<()> {
-    let (instance_config, version) = {
-        let instance_manager = launcher.instance_manager.lock().await;
-        if let Some(config) = instance_manager.get_instance(instance_name) {
-            let config_clone = config.clone();
-            let version = config.version.clone();
-            (Some(config_clone), version)
-        } else {
-            return Err(crate::error::InstanceError::not_found(format!(
-                "Instance '{instance_name}' does not exist. Use 'rustified instance list' to see available instances or 'rustified instance create' to create one."
-            )).into());
-        }
-    };
+    if export_script.is_some() && cfg!(windows) {
+        return Err(crate::error::GameError::launch_failed(
+            "--export-script writes a POSIX shell script, which Windows cannot run".to_string(),
+        )
+        .into());
+    }
+    let dry_run = options.mode == launcher::LaunchMode::DryRun;
+
+    let (instance_config, instance_lock) = lock_instance(launcher, instance_name, dry_run).await?;
+    let version = instance_config.version.clone();
+    let instance_config = Some(instance_config);
 
     let resolved_version = super::game::resolve_version_alias(launcher, &version).await?;
 
@@ -225,8 +233,8 @@ pub async fn launch_game(
         )).into());
     }
 
-    // Update last used timestamp
-    {
+    // Update last used timestamp, dry runs do not count as playing
+    if !dry_run {
         let mut instance_manager = launcher.instance_manager.lock().await;
         instance_manager.update_last_used(instance_name).await?;
     }
@@ -247,24 +255,161 @@ pub async fn launch_game(
         }
     };
 
-    // Prepare the game (download if necessary)
-    info!("Preparing game files...");
-    launcher
-        .prepare_game(&resolved_version, &auth_result)
-        .await?;
-    info!("✓ Game files prepared successfully");
+    // Prepare the game (download if necessary), dry runs only use what is on disk
+    if dry_run {
+        info!("Using the game files already downloaded, 'rustified prepare' fetches missing ones");
+    } else {
+        info!("Preparing game files...");
+        launcher
+            .prepare_game(&resolved_version, &auth_result)
+            .await?;
+        info!("✓ Game files prepared successfully");
+    }
 
     // Launch the game
     info!("Starting Minecraft {resolved_version}...");
 
-    launcher
-        .launch_game(&resolved_version, &auth_result, instance_config.as_ref())
-        .await?;
+    let session = match launcher
+        .launch_game(
+            &resolved_version,
+            &auth_result,
+            instance_config.as_ref(),
+            instance_lock.as_ref(),
+            options,
+        )
+        .await?
+    {
+        launcher::LaunchOutcome::Finished(session) => session,
+        launcher::LaunchOutcome::Detached(game) => {
+            info!("✓ Minecraft started in the background (PID {})", game.pid);
+            info!("Game output is written to {}", game.log_path.display());
+            info!("Its exit and playtime are not recorded in the launch history");
+            let record = launcher::history::LaunchRecord::from_detached(&game, &resolved_version);
+            record_launch(launcher, instance_name, record).await;
+            return Ok(());
+        }
+        launcher::LaunchOutcome::DryRun(plan) => {
+            if let Some(path) = export_script {
+                plan.write_script(path)?;
+                info!("✓ Launch script written to {}", path.display());
+                warn!("The script contains your access token, do not share it");
+            }
+            print_launch_plan(&plan);
+            return Ok(());
+        }
+    };
+
+    // Record the session even if the game crashed
+    let record = launcher::history::LaunchRecord::from_session(&session, &resolved_version);
+    record_launch(launcher, instance_name, record).await;
+
+    if session.crashed() {
+        report_crash(&session, instance_name);
+        return Err(crate::error::GameError::launch_failed(format!(
+            "Minecraft exited with code: {:?} (output saved to {})",
+            session.exit_code,
+            session.log_path.display()
+        ))
+        .into());
+    }
     info!("✓ Minecraft exited");
 
     Ok(())
 }
 
+/// Looks up an instance and, unless this is a dry run, locks it for the launch
+///
+/// The lock is held until the game exits so other launcher processes leave the
+/// instance alone, and a game started in the background inherits it. Dry runs
+/// change nothing and work while the game is running.
+async fn lock_instance(
+    launcher: &launcher::Launcher,
+    instance_name: &str,
+    dry_run: bool,
+) -> crate::error::Result<(launcher::InstanceConfig, Option<crate::fs_utils::FileLock>)> {
+    let instance_manager = launcher.instance_manager.lock().await;
+    let Some(config) = instance_manager.get_instance(instance_name) else {
+        return Err(crate::error::InstanceError::not_found(format!(
+            "Instance '{instance_name}' does not exist. Use 'rustified instance list' to see available instances or 'rustified instance create' to create one."
+        )).into());
+    };
+
+    let instance_lock = if dry_run {
+        None
+    } else {
+        Some(instance_manager.lock_instance(instance_name).await?)
+    };
+    Ok((config.clone(), instance_lock))
+}
+
+/// Adds a launch to the instance's history, warning instead of failing
+async fn record_launch(
+    launcher: &launcher::Launcher,
+    instance_name: &str,
+    record: launcher::history::LaunchRecord,
+) {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    if let Err(e) = instance_manager.record_launch(instance_name, record).await {
+        warn!("Failed to record launch history: {e}");
+    }
+}
+
+/// Prints the command a launch would run, with secrets redacted
+fn print_launch_plan(plan: &launcher::LaunchPlan) {
+    info!("Program: {}", plan.program.display());
+    if let Some(dir) = &plan.working_dir {
+        info!("Working directory: {}", dir.display());
+    }
+    for (key, value) in &plan.env {
+        if let Some(value) = value {
+            info!("Environment: {key}={}", plan.redact(value));
+        } else {
+            info!("Environment: unset {key}");
+        }
+    }
+    info!("Arguments:");
+    for arg in plan.redacted_args() {
+        info!("  {arg}");
+    }
+    info!("Command: {}", plan.command_line());
+}
+
+/// Prints what is known about why a session crashed and how to fix it
+fn report_crash(session: &launcher::GameSession, instance_name: &str) {
+    let analysis = launcher::crash::analyze(session, instance_name);
+
+    if let Some(report) = &analysis.report {
+        let label = match report.kind {
+            launcher::crash::CrashReportKind::Minecraft => "Crash report",
+            launcher::crash::CrashReportKind::JvmFatalError => "JVM error log",
+        };
+        error!("{label}: {}", report.path.display());
+        if let Some(description) = &report.description {
+            error!("  Description: {description}");
+        }
+        for line in &report.stack_trace {
+            error!("    {line}");
+        }
+    }
+
+    if analysis.diagnoses.is_empty() {
+        warn!("Could not determine the cause of the crash");
+        warn!(
+            "Check the game output in {} for details",
+            session.log_path.display()
+        );
+        return;
+    }
+
+    for diagnosis in &analysis.diagnoses {
+        warn!("Likely cause: {}", diagnosis.title);
+        warn!("  {}", diagnosis.explanation);
+        for fix in &diagnosis.fixes {
+            info!("  → {fix}");
+        }
+    }
+}
+
 /// Resolves a version alias (like "latest-release" or "latest-snapshot") to a concrete Minecraft version string.
 ///
 /// # Errors
@@ -1,5 +1,24 @@
-use crate::cli::InstanceCommands;
-use tracing::{error, info};
+use crate::cli::{InstanceCommands, InstanceSortOrder, ModLoaderFilter, OutputFormat};
+use crate::launcher::environment;
+use crate::launcher::history::format_duration;
+use crate::launcher::jvm::JvmPreset;
+use crate::launcher::memory::{self, HeapSize, MemorySize};
+use crate::launcher::{InstanceConfig, JavaManager, ModLoader, ServerConfig};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::{error, info, warn};
+
+/// Options for listing instances
+#[derive(Debug)]
+pub struct ListInstancesOptions {
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    pub version: Option<String>,
+    pub loader: Option<ModLoaderFilter>,
+    pub filter: Option<String>,
+    pub sort: InstanceSortOrder,
+    pub format: OutputFormat,
+}
 
 /// Handles all instance-related commands.
 ///
@@ -10,95 +29,800 @@ pub async fn handle_instance_command(
     action: InstanceCommands,
 ) -> crate::error::Result<()> {
     match action {
-        InstanceCommands::List => {
-            let instance_manager = launcher.instance_manager.lock().await;
-            let instances: Vec<_> = instance_manager
-                .list_instances()
-                .into_iter()
-                .cloned()
-                .collect();
-            drop(instance_manager); // Release lock early
-
-            if instances.is_empty() {
-                info!(
-                    "No instances found. Create one with: rustified instance create <name> <version>"
-                );
-            } else {
-                info!("Available instances:");
-                for instance in instances {
-                    let last_used = if let Some(used) = instance.last_used {
-                        format!(" (last used: {})", used.format("%Y-%m-%d %H:%M:%S"))
-                    } else {
-                        String::new()
-                    };
-
-                    let description = instance
-                        .description
-                        .as_ref()
-                        .map(|d| format!(" - {d}"))
-                        .unwrap_or_default();
-
-                    info!(
-                        "  {} (v{}){}{}",
-                        instance.name, instance.version, description, last_used
-                    );
-                }
-            }
+        InstanceCommands::List {
+            tag,
+            group,
+            version,
+            loader,
+            filter,
+            sort,
+            format,
+            json,
+        } => {
+            let options = ListInstancesOptions {
+                tags: tag,
+                group,
+                version,
+                loader,
+                filter,
+                sort,
+                format: if json { OutputFormat::Json } else { format },
+            };
+            list_instances(launcher, options).await?;
         }
         InstanceCommands::Info { name } => {
-            let instance_manager = launcher.instance_manager.lock().await;
-            if let Some(instance) = instance_manager.get_instance(&name) {
-                let instance = instance.clone(); // Clone to avoid borrow issues
-                drop(instance_manager); // Release lock
-
-                info!("Instance: {}", instance.name);
-                info!("  Version: {}", instance.version);
-                if let Some(desc) = &instance.description {
-                    info!("  Description: {desc}");
-                }
-                info!(
-                    "  Created: {}",
-                    instance.created.format("%Y-%m-%d %H:%M:%S")
-                );
-                if let Some(used) = instance.last_used {
-                    info!("  Last used: {}", used.format("%Y-%m-%d %H:%M:%S"));
-                }
-                info!("  Mod loader: {:?}", instance.mods.loader);
-                if let Some(memory) = instance.settings.memory_mb {
-                    info!("  Memory: {memory}MB");
-                }
-                if !instance.settings.java_args.is_empty() {
-                    info!("  Java args: {}", instance.settings.java_args.join(" "));
-                }
-            } else {
-                error!("Instance '{name}' does not exist");
-                return Err(crate::error::InstanceError::not_found(
-                    "Instance not found".to_string(),
-                )
-                .into());
-            }
+            show_instance_info(launcher, &name).await?;
+        }
+        InstanceCommands::History { name, limit } => {
+            show_instance_history(launcher, &name, limit).await?;
         }
         InstanceCommands::Create {
             name,
             version,
             description,
-        } => {
-            let mut instance_manager = launcher.instance_manager.lock().await;
-            instance_manager
-                .create_instance(name.clone(), version, description, &launcher.file_manager)
-                .await?;
-            info!("✓ Created instance '{name}'");
+            group,
+            tag,
+        } => create_instance(launcher, name, version, description, group, &tag).await?,
+        InstanceCommands::Tag { name, add, remove } => {
+            tag_instance(launcher, &name, &add, &remove).await?;
+        }
+        InstanceCommands::Group { name, group } => {
+            set_instance_group(launcher, &name, group).await?;
         }
         InstanceCommands::Delete { name } => {
             let mut instance_manager = launcher.instance_manager.lock().await;
             instance_manager.delete_instance(&name).await?;
             info!("✓ Deleted instance '{name}'");
         }
-        InstanceCommands::Memory { name, memory } => {
-            let mut instance_manager = launcher.instance_manager.lock().await;
-            instance_manager.set_instance_memory(&name, memory).await?;
-            info!("✓ Set memory for instance '{name}' to {memory}MB");
+        InstanceCommands::Memory {
+            name,
+            memory,
+            min,
+            reset,
+        } => set_instance_memory(launcher, &name, memory, min, reset).await?,
+        InstanceCommands::Java {
+            name,
+            path,
+            version,
+            auto: _,
+        } => set_instance_java(launcher, &name, path, version).await?,
+        InstanceCommands::QuickPlay {
+            name,
+            server,
+            world,
+            clear: _,
+        } => set_instance_quick_play(launcher, &name, server, world).await?,
+        InstanceCommands::Window {
+            name,
+            width,
+            height,
+            fullscreen,
+            windowed,
+            reset,
+        } => {
+            let fullscreen = (fullscreen || windowed).then_some(fullscreen);
+            set_instance_window(launcher, &name, width, height, fullscreen, reset).await?;
         }
+        InstanceCommands::Hooks {
+            name,
+            pre_launch,
+            post_exit,
+            wrapper,
+            clear,
+        } => {
+            set_instance_hooks(launcher, &name, pre_launch, post_exit, wrapper, clear).await?;
+        }
+        InstanceCommands::Env {
+            name,
+            set,
+            unset,
+            reset,
+            sanitize,
+            inherit,
+        } => set_instance_env(launcher, &name, &set, &unset, &reset, sanitize, inherit).await?,
+        InstanceCommands::Preset { name, preset } => {
+            set_instance_jvm_preset(launcher, &name, preset.into()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Sets the initial and maximum heap of an instance, keeping the one not given
+async fn set_instance_memory(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    max: Option<String>,
+    min: Option<String>,
+    reset: bool,
+) -> crate::error::Result<()> {
+    let max = max.as_deref().map(MemorySize::parse).transpose()?;
+    let min = min.as_deref().map(MemorySize::parse).transpose()?;
+
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    let settings = instance_manager
+        .get_instance(name)
+        .map(|config| config.settings.clone())
+        .ok_or_else(|| {
+            crate::error::InstanceError::not_found(format!("Instance '{name}' does not exist"))
+        })?;
+    let (min, max) = if reset {
+        (None, None)
+    } else {
+        (min.or(settings.min_memory), max.or(settings.max_memory))
+    };
+    instance_manager.set_instance_memory(name, min, max).await?;
+
+    info!("✓ Instance '{name}' memory: {}", describe_memory(min, max));
+    if let Some(total) = memory::total_memory_mb() {
+        let heap = HeapSize::resolve(min, max, Some(total));
+        info!(
+            "  This machine has {total}MB, the game gets -Xms{}M -Xmx{}M",
+            heap.min_mb, heap.max_mb
+        );
+    }
+    Ok(())
+}
+
+/// Human-readable heap settings, e.g. "2G initial, 50% maximum"
+fn describe_memory(min: Option<MemorySize>, max: Option<MemorySize>) -> String {
+    format!(
+        "{} initial, {} maximum",
+        min.map_or_else(|| "default".to_string(), |size| size.to_string()),
+        max.map_or_else(|| "default".to_string(), |size| size.to_string())
+    )
+}
+
+/// Moves an instance to a group, or out of its group
+async fn set_instance_group(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    group: Option<String>,
+) -> crate::error::Result<()> {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    instance_manager
+        .set_instance_group(name, group.clone())
+        .await?;
+    if let Some(group) = group {
+        info!("✓ Moved instance '{name}' to group '{group}'");
+    } else {
+        info!("✓ Removed instance '{name}' from its group");
     }
     Ok(())
 }
+
+/// Adds and removes tags on an instance and shows the result
+async fn tag_instance(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    add: &[String],
+    remove: &[String],
+) -> crate::error::Result<()> {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    instance_manager
+        .update_instance_tags(name, add, remove)
+        .await?;
+    let tags = instance_manager
+        .get_instance(name)
+        .map(|i| i.tags.join(", "))
+        .unwrap_or_default();
+    info!("✓ Tags for instance '{name}': [{tags}]");
+    Ok(())
+}
+
+/// Creates an instance with its initial tags and group
+async fn create_instance(
+    launcher: &crate::launcher::Launcher,
+    name: String,
+    version: String,
+    description: Option<String>,
+    group: Option<String>,
+    tags: &[String],
+) -> crate::error::Result<()> {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    instance_manager
+        .create_instance(
+            name.clone(),
+            version,
+            description,
+            group,
+            tags,
+            &launcher.file_manager,
+        )
+        .await?;
+    info!("✓ Created instance '{name}'");
+    Ok(())
+}
+
+/// Changes the window settings given and keeps the others, or resets all of them
+async fn set_instance_window(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    width: Option<u32>,
+    height: Option<u32>,
+    fullscreen: Option<bool>,
+    reset: bool,
+) -> crate::error::Result<()> {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    let settings = instance_manager
+        .get_instance(name)
+        .map(|config| config.settings.clone())
+        .ok_or_else(|| {
+            crate::error::InstanceError::not_found(format!("Instance '{name}' does not exist"))
+        })?;
+
+    let (width, height, fullscreen) = if reset {
+        (None, None, false)
+    } else {
+        (
+            width.or(settings.width),
+            height.or(settings.height),
+            fullscreen.unwrap_or(settings.fullscreen),
+        )
+    };
+    instance_manager
+        .set_instance_window(name, width, height, fullscreen)
+        .await?;
+
+    info!(
+        "✓ Instance '{name}' window: {}",
+        describe_window(width, height, fullscreen)
+    );
+    Ok(())
+}
+
+/// Selects the garbage collector flag preset of an instance
+async fn set_instance_jvm_preset(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    preset: JvmPreset,
+) -> crate::error::Result<()> {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    instance_manager
+        .set_instance_jvm_preset(name, preset)
+        .await?;
+    info!("✓ Instance '{name}' will use the {preset:?} JVM preset");
+    Ok(())
+}
+
+/// Updates the environment variables of an instance and shows the result
+async fn set_instance_env(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    set: &[String],
+    unset: &[String],
+    reset: &[String],
+    sanitize: bool,
+    inherit: bool,
+) -> crate::error::Result<()> {
+    let invalid = |entry: &str| {
+        crate::error::InstanceError::invalid_config(format!(
+            "Invalid environment variable '{entry}', expected NAME=VALUE"
+        ))
+    };
+    let mut changes = Vec::new();
+    for entry in set {
+        let (key, value) = entry.split_once('=').ok_or_else(|| invalid(entry))?;
+        changes.push((key, Some(value.to_string())));
+    }
+    changes.extend(unset.iter().map(|key| (key.as_str(), None)));
+    if let Some((key, _)) = changes
+        .iter()
+        .find(|(key, _)| !environment::is_valid_name(key))
+    {
+        return Err(invalid(key).into());
+    }
+
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    let settings = instance_manager
+        .get_instance(name)
+        .map(|config| config.settings.clone())
+        .ok_or_else(|| {
+            crate::error::InstanceError::not_found(format!("Instance '{name}' does not exist"))
+        })?;
+
+    let mut env = settings.env;
+    for key in reset {
+        env.remove(key);
+    }
+    for (key, value) in changes {
+        env.insert(key.to_string(), value);
+    }
+    let sanitize_env = sanitize || (settings.sanitize_env && !inherit);
+    instance_manager
+        .set_instance_env(name, env.clone(), sanitize_env)
+        .await?;
+
+    info!("✓ Instance '{name}' environment updated");
+    for line in describe_env(&env, sanitize_env) {
+        info!("  {line}");
+    }
+    Ok(())
+}
+
+/// Human-readable environment settings, one line per entry
+fn describe_env(env: &BTreeMap<String, Option<String>>, sanitize_env: bool) -> Vec<String> {
+    let mut lines: Vec<String> = env
+        .iter()
+        .map(|(key, value)| match value {
+            Some(value) => format!("{key}={value}"),
+            None => format!("{key} (unset)"),
+        })
+        .collect();
+    if sanitize_env {
+        lines.push(format!("Removes {}", environment::JVM_VARIABLES.join(", ")));
+    }
+    if lines.is_empty() {
+        lines.push("Inherited from the launcher".to_string());
+    }
+    lines
+}
+
+/// Sets the hooks and wrapper of an instance, keeping the ones not given
+async fn set_instance_hooks(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    pre_launch: Option<String>,
+    post_exit: Option<String>,
+    wrapper: Option<String>,
+    clear: bool,
+) -> crate::error::Result<()> {
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    let settings = instance_manager
+        .get_instance(name)
+        .map(|config| config.settings.clone())
+        .ok_or_else(|| {
+            crate::error::InstanceError::not_found(format!("Instance '{name}' does not exist"))
+        })?;
+
+    // An empty command removes that hook
+    let merge = |new: Option<String>, current: Option<String>| {
+        if clear {
+            return None;
+        }
+        new.map_or(current, |command| {
+            let command = command.trim();
+            (!command.is_empty()).then(|| command.to_string())
+        })
+    };
+    let pre_launch = merge(pre_launch, settings.pre_launch);
+    let post_exit = merge(post_exit, settings.post_exit);
+    let wrapper = merge(wrapper, settings.wrapper);
+    instance_manager
+        .set_instance_hooks(name, pre_launch.clone(), post_exit.clone(), wrapper.clone())
+        .await?;
+
+    info!("✓ Instance '{name}' hooks updated");
+    info!("  Pre-launch: {}", pre_launch.as_deref().unwrap_or("none"));
+    info!("  Post-exit: {}", post_exit.as_deref().unwrap_or("none"));
+    info!("  Wrapper: {}", wrapper.as_deref().unwrap_or("none"));
+    Ok(())
+}
+
+/// Human-readable window settings, e.g. "1280x720, fullscreen"
+fn describe_window(width: Option<u32>, height: Option<u32>, fullscreen: bool) -> String {
+    let size = match (width, height) {
+        (None, None) => "default size".to_string(),
+        (width, height) => format!(
+            "{}x{}",
+            width.map_or_else(|| "default".to_string(), |w| w.to_string()),
+            height.map_or_else(|| "default".to_string(), |h| h.to_string())
+        ),
+    };
+    if fullscreen {
+        format!("{size}, fullscreen")
+    } else {
+        size
+    }
+}
+
+/// Sets the server or world an instance joins on launch, or clears it
+async fn set_instance_quick_play(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    server: Option<String>,
+    world: Option<String>,
+) -> crate::error::Result<()> {
+    let server = server.as_deref().map(ServerConfig::parse).transpose()?;
+
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    instance_manager
+        .set_instance_quick_play(name, server.clone(), world.clone())
+        .await?;
+
+    if let Some(server) = server {
+        info!("✓ Instance '{name}' will join {server} on launch");
+    } else if let Some(world) = world {
+        info!("✓ Instance '{name}' will open world '{world}' on launch");
+    } else {
+        info!("✓ Instance '{name}' will start at the title screen");
+    }
+    Ok(())
+}
+
+/// Pins an instance to a Java executable or major version, or clears the override
+///
+/// # Errors
+///
+/// Returns an error if the given Java executable cannot be run or the instance cannot be saved.
+async fn set_instance_java(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    path: Option<PathBuf>,
+    version: Option<u32>,
+) -> crate::error::Result<()> {
+    if let Some(path) = &path {
+        // Make sure the executable actually runs before saving it
+        let installation = JavaManager::probe_java_installation(path)?;
+        info!(
+            "Validated Java {} at {}",
+            installation.major_version,
+            installation.path.display()
+        );
+    } else if let Some(major) = version
+        && !launcher.java_manager.lock().await.has_major(major)
+    {
+        warn!("Java {major} is not currently installed; launches will fail until it is");
+    }
+
+    let mut instance_manager = launcher.instance_manager.lock().await;
+    instance_manager
+        .set_instance_java(name, path.clone(), version)
+        .await?;
+    match (path, version) {
+        (Some(path), _) => info!("✓ Instance '{name}' will use Java at {}", path.display()),
+        (None, Some(major)) => info!("✓ Instance '{name}' will use Java {major}"),
+        (None, None) => info!("✓ Instance '{name}' will pick Java automatically"),
+    }
+    Ok(())
+}
+
+/// Lists instances matching the given filters, including ones whose configuration failed to load
+///
+/// # Errors
+///
+/// Returns an error if serializing the JSON output fails.
+async fn list_instances(
+    launcher: &crate::launcher::Launcher,
+    options: ListInstancesOptions,
+) -> crate::error::Result<()> {
+    let instance_manager = launcher.instance_manager.lock().await;
+    let mut instances: Vec<_> = instance_manager
+        .list_instances()
+        .into_iter()
+        .filter(|instance| matches_filters(instance, &options))
+        .cloned()
+        .collect();
+    let broken_instances = instance_manager.list_broken_instances().to_vec();
+    drop(instance_manager); // Release lock early
+
+    // Sort instances according to the specified order
+    match options.sort {
+        InstanceSortOrder::Name => {
+            instances.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        InstanceSortOrder::LastUsed => {
+            instances.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.name.cmp(&b.name)));
+        }
+        InstanceSortOrder::Created => {
+            instances.sort_by(|a, b| b.created.cmp(&a.created).then(a.name.cmp(&b.name)));
+        }
+        InstanceSortOrder::Playtime => {
+            instances.sort_by(|a, b| {
+                b.playtime_secs
+                    .cmp(&a.playtime_secs)
+                    .then(a.name.cmp(&b.name))
+            });
+        }
+    }
+
+    if matches!(options.format, OutputFormat::Json) {
+        let output = serde_json::json!({
+            "instances": instances,
+            "broken": broken_instances,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if instances.is_empty() && broken_instances.is_empty() {
+        info!("No instances found. Create one with: rustified instance create <name> <version>");
+        return Ok(());
+    }
+
+    info!("Available instances:");
+    match options.format {
+        OutputFormat::Table => print_instance_table(&instances),
+        _ => {
+            for instance in &instances {
+                print_instance_line(instance);
+            }
+        }
+    }
+    for broken in broken_instances {
+        warn!("  {} (broken: {})", broken.name, broken.error);
+    }
+
+    Ok(())
+}
+
+/// Checks whether an instance matches all filters given on the command line
+fn matches_filters(instance: &InstanceConfig, options: &ListInstancesOptions) -> bool {
+    let has_tags = options.tags.iter().all(|wanted| {
+        instance
+            .tags
+            .iter()
+            .any(|tag| tag.eq_ignore_ascii_case(wanted))
+    });
+
+    let in_group = options.group.as_ref().is_none_or(|wanted| {
+        instance
+            .group
+            .as_ref()
+            .is_some_and(|group| group.eq_ignore_ascii_case(wanted))
+    });
+
+    let version_matches = options
+        .version
+        .as_ref()
+        .is_none_or(|prefix| instance.version.starts_with(prefix.as_str()));
+
+    let loader_matches = options.loader.as_ref().is_none_or(|loader| {
+        matches!(
+            (loader, &instance.mods.loader),
+            (ModLoaderFilter::Vanilla, ModLoader::Vanilla)
+                | (ModLoaderFilter::Forge, ModLoader::Forge)
+                | (ModLoaderFilter::Fabric, ModLoader::Fabric)
+                | (ModLoaderFilter::Quilt, ModLoader::Quilt)
+        )
+    });
+
+    let name_matches = options.filter.as_ref().is_none_or(|pattern| {
+        instance
+            .name
+            .to_lowercase()
+            .contains(&pattern.to_lowercase())
+    });
+
+    has_tags && in_group && version_matches && loader_matches && name_matches
+}
+
+/// Prints a single instance as one line
+fn print_instance_line(instance: &InstanceConfig) {
+    let last_used = if let Some(used) = instance.last_used {
+        format!(" (last used: {})", used.format("%Y-%m-%d %H:%M:%S"))
+    } else {
+        String::new()
+    };
+
+    let description = instance
+        .description
+        .as_ref()
+        .map(|d| format!(" - {d}"))
+        .unwrap_or_default();
+
+    let group = instance
+        .group
+        .as_ref()
+        .map(|g| format!(" @{g}"))
+        .unwrap_or_default();
+
+    let tags = if instance.tags.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", instance.tags.join(", "))
+    };
+
+    info!(
+        "  {} (v{}){}{}{}{}",
+        instance.name, instance.version, group, tags, description, last_used
+    );
+}
+
+/// Prints instances as an aligned table
+fn print_instance_table(instances: &[InstanceConfig]) {
+    let headers = [
+        "NAME",
+        "VERSION",
+        "LOADER",
+        "GROUP",
+        "TAGS",
+        "PLAYTIME",
+        "LAST USED",
+    ];
+    let rows: Vec<[String; 7]> = instances
+        .iter()
+        .map(|instance| {
+            [
+                instance.name.clone(),
+                instance.version.clone(),
+                format!("{:?}", instance.mods.loader).to_lowercase(),
+                instance.group.clone().unwrap_or_else(|| "-".to_string()),
+                if instance.tags.is_empty() {
+                    "-".to_string()
+                } else {
+                    instance.tags.join(",")
+                },
+                format_duration(instance.playtime_secs),
+                instance.last_used.map_or_else(
+                    || "never".to_string(),
+                    |used| used.format("%Y-%m-%d %H:%M").to_string(),
+                ),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    info!("  {}", format_row(&headers.map(str::to_string)));
+    for row in &rows {
+        info!("  {}", format_row(row));
+    }
+}
+
+/// Shows the details of a single instance
+async fn show_instance_info(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+) -> crate::error::Result<()> {
+    let instance_manager = launcher.instance_manager.lock().await;
+    if let Some(instance) = instance_manager.get_instance(name) {
+        let instance = instance.clone(); // Clone to avoid borrow issues
+        drop(instance_manager); // Release lock
+
+        info!("Instance: {}", instance.name);
+        info!("  Version: {}", instance.version);
+        if let Some(desc) = &instance.description {
+            info!("  Description: {desc}");
+        }
+        if let Some(group) = &instance.group {
+            info!("  Group: {group}");
+        }
+        if !instance.tags.is_empty() {
+            info!("  Tags: {}", instance.tags.join(", "));
+        }
+        info!(
+            "  Created: {}",
+            instance.created.format("%Y-%m-%d %H:%M:%S")
+        );
+        if let Some(used) = instance.last_used {
+            info!("  Last used: {}", used.format("%Y-%m-%d %H:%M:%S"));
+        }
+        info!(
+            "  Playtime: {} over {} launch(es)",
+            format_duration(instance.playtime_secs),
+            instance.launch_count
+        );
+        info!("  Mod loader: {:?}", instance.mods.loader);
+        if instance.settings.min_memory.is_some() || instance.settings.max_memory.is_some() {
+            info!(
+                "  Memory: {}",
+                describe_memory(instance.settings.min_memory, instance.settings.max_memory)
+            );
+        }
+        if let Some(path) = &instance.settings.java_path {
+            info!("  Java: {} (override)", path.display());
+        } else if let Some(major) = instance.settings.java_version {
+            info!("  Java: {major} (pinned)");
+        }
+        info!("  JVM preset: {:?}", instance.settings.jvm_preset);
+        if instance.settings.width.is_some()
+            || instance.settings.height.is_some()
+            || instance.settings.fullscreen
+        {
+            info!(
+                "  Window: {}",
+                describe_window(
+                    instance.settings.width,
+                    instance.settings.height,
+                    instance.settings.fullscreen
+                )
+            );
+        }
+        if let Some(server) = &instance.settings.server {
+            info!("  Joins server: {server}");
+        } else if let Some(world) = &instance.settings.world {
+            info!("  Opens world: {world}");
+        }
+        if !instance.settings.java_args.is_empty() {
+            info!("  Java args: {}", instance.settings.java_args.join(" "));
+        }
+        if !instance.settings.env.is_empty() || instance.settings.sanitize_env {
+            info!("  Environment:");
+            for line in describe_env(&instance.settings.env, instance.settings.sanitize_env) {
+                info!("    {line}");
+            }
+        }
+        if let Some(wrapper) = &instance.settings.wrapper {
+            info!("  Wrapper: {wrapper}");
+        }
+        if let Some(pre_launch) = &instance.settings.pre_launch {
+            info!("  Pre-launch hook: {pre_launch}");
+        }
+        if let Some(post_exit) = &instance.settings.post_exit {
+            info!("  Post-exit hook: {post_exit}");
+        }
+    } else if let Some(broken) = instance_manager.get_broken_instance(name) {
+        error!(
+            "Instance '{name}' could not be loaded from {}",
+            broken.config_path.display()
+        );
+        return Err(crate::error::InstanceError::invalid_config(broken.error.clone()).into());
+    } else {
+        error!("Instance '{name}' does not exist");
+        return Err(
+            crate::error::InstanceError::not_found("Instance not found".to_string()).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Shows the most recent game sessions of an instance, newest first
+async fn show_instance_history(
+    launcher: &crate::launcher::Launcher,
+    name: &str,
+    limit: usize,
+) -> crate::error::Result<()> {
+    let instance_manager = launcher.instance_manager.lock().await;
+    let history = instance_manager.get_launch_history(name).await?;
+    drop(instance_manager);
+
+    if history.is_empty() {
+        info!("Instance '{name}' has not been launched yet");
+        return Ok(());
+    }
+
+    info!(
+        "Showing {} of {} recorded session(s) for '{name}':",
+        history.len().min(limit),
+        history.len()
+    );
+    for record in history.iter().rev().take(limit) {
+        let status = if record.ended_at.is_none() {
+            "started in the background".to_string()
+        } else if record.stopped_by_user {
+            "stopped".to_string()
+        } else if let Some(signal) = record.signal {
+            format!("crashed (signal {signal})")
+        } else if record.crashed {
+            format!("crashed (exit code {:?})", record.exit_code)
+        } else {
+            "ok".to_string()
+        };
+        let line = format!(
+            "  {} - {} on Minecraft {} with Java {} - {status}",
+            record.started_at.format("%Y-%m-%d %H:%M:%S"),
+            record.ended_at.map_or_else(
+                || "unknown length".to_string(),
+                |_| format_duration(record.duration_secs())
+            ),
+            record.minecraft_version,
+            record.java_version
+        );
+        if record.crashed {
+            warn!("{line}");
+            if let Some(report) = &record.crash_report {
+                warn!("      crash report: {}", report.display());
+            }
+        } else {
+            info!("{line}");
+        }
+    }
+
+    Ok(())
+}
@@ -1,28 +1,150 @@
-use std::borrow::Borrow;
-
 use crate::cli::JavaCommands;
-use tracing::info;
-
-pub fn handle_java_command(launcher: &crate::launcher::Launcher, action: JavaCommands) {
-    // Lazily initialize Java installations for Java commands
-    let java_manager = launcher.java_manager.borrow();
+use crate::error::GameError;
+use crate::launcher::java::{JavaRequirement, JavaRequirementSource};
+use crate::launcher::{JavaManager, VersionInfo};
+use tracing::{info, warn};
 
+/// Handles all Java-related commands.
+///
+/// # Errors
+/// Returns an error if registering a Java installation, fetching version info or installing a
+/// Java runtime fails.
+pub async fn handle_java_command(
+    launcher: &crate::launcher::Launcher,
+    action: JavaCommands,
+) -> crate::error::Result<()> {
     match action {
-        JavaCommands::List => {
-            let installations = &java_manager.installations;
-            if installations.is_empty() {
-                info!("No Java installations found. Try installing Java or setting JAVA_HOME.");
-            } else {
-                info!("Found {} Java installation(s):", installations.len());
-                for (major, installation) in installations {
-                    info!("  Java {}: {}", major, installation.path.display());
-                }
-            }
+        JavaCommands::List { refresh } => list_java_installations(launcher, refresh).await,
+        JavaCommands::Add { path } => {
+            let installation = launcher
+                .java_manager
+                .lock()
+                .await
+                .add_user_java(&launcher.minecraft_dir, &path)
+                .await?;
+            info!(
+                "✓ Registered Java {} ({}): {}",
+                installation.major_version,
+                installation.describe(),
+                installation.path.display()
+            );
+        }
+        JavaCommands::Remove { path } => {
+            launcher
+                .java_manager
+                .lock()
+                .await
+                .remove_user_java(&launcher.minecraft_dir, &path)
+                .await?;
+            info!("✓ Unregistered Java installation: {}", path.display());
         }
         JavaCommands::Recommend { version } => {
             info!("Getting recommended Java version for Minecraft {version}...");
-            let recommended = crate::launcher::JavaManager::get_required_java_version(&version);
-            info!("Recommended Java version: {recommended}");
+            let requirement = match load_version_info(launcher, &version).await {
+                Some(version_info) => JavaManager::required_java(&version_info),
+                None => JavaRequirement {
+                    major: JavaManager::get_required_java_version(&version),
+                    max_major: None,
+                    source: JavaRequirementSource::VersionTable,
+                },
+            };
+            info!(
+                "Recommended Java version: {} ({})",
+                requirement.major, requirement.source
+            );
+            if let Some(max_major) = requirement.max_major {
+                info!("Newest supported Java version: {max_major}");
+            }
+        }
+        JavaCommands::Install { version } => {
+            let version_info = launcher.file_manager.get_version_info(&version).await?;
+            let required = version_info.java_version.ok_or_else(|| {
+                GameError::invalid_version(format!(
+                    "Minecraft {version} does not name a Java runtime component"
+                ))
+            })?;
+
+            let installation = launcher.install_java_runtime(&required.component).await?;
+            info!(
+                "✓ Installed Java {} ({}) at {}",
+                installation.major_version,
+                required.component,
+                installation.path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Lists discovered Java installations, best first within each major version
+async fn list_java_installations(launcher: &crate::launcher::Launcher, refresh: bool) {
+    let mut java_manager = launcher.java_manager.lock().await;
+    if refresh {
+        info!("Rescanning Java installations...");
+        java_manager.refresh(&launcher.minecraft_dir).await;
+    }
+    if java_manager.installations.is_empty() {
+        info!("No Java installations found. Try installing Java or setting JAVA_HOME.");
+    } else {
+        let runtime_dir = launcher.minecraft_dir.runtime_dir();
+        let mut installations: Vec<_> = java_manager.installations.iter().collect();
+        installations.sort_by(|a, b| {
+            b.major_version
+                .cmp(&a.major_version)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+
+        info!("Found {} Java installation(s):", installations.len());
+        for installation in installations {
+            let mut labels = Vec::new();
+            if java_manager
+                .best_for_major(installation.major_version)
+                .is_some_and(|best| best.path == installation.path)
+            {
+                labels.push("preferred");
+            }
+            if installation.path.starts_with(&runtime_dir) {
+                labels.push("managed");
+            }
+            if !installation.is_native() {
+                labels.push("non-native");
+            }
+            let labels = if labels.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", labels.join(", "))
+            };
+
+            info!(
+                "  Java {} ({}): {}{labels}",
+                installation.major_version,
+                installation.describe(),
+                installation.path.display()
+            );
+        }
+    }
+}
+
+/// Version JSON from the local install, or from Mojang if it is not installed
+async fn load_version_info(
+    launcher: &crate::launcher::Launcher,
+    version: &str,
+) -> Option<VersionInfo> {
+    let json_path = launcher.minecraft_dir.version_json_path(version);
+    if let Ok(content) = tokio::fs::read_to_string(&json_path).await
+        && let Ok(version_info) = serde_json::from_str(&content)
+    {
+        return Some(version_info);
+    }
+
+    match launcher.file_manager.get_version_info(version).await {
+        Ok(version_info) => Some(version_info),
+        Err(e) => {
+            warn!(
+                "Could not load version JSON for {version}: {}",
+                e.to_string().lines().next().unwrap_or_default()
+            );
+            None
         }
     }
 }
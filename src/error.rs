@@ -108,6 +108,9 @@ pub enum InstanceError {
     /// Instance deletion failed
     #[error("Instance deletion failed: {0}")]
     DeletionFailed(String),
+    /// Instance is locked by another launcher process
+    #[error("Instance is in use: {0}")]
+    InUse(String),
 }
 
 /// File manager errors
@@ -372,6 +375,10 @@ impl InstanceError {
     pub fn deletion_failed(msg: impl Into<String>) -> Self {
         Self::DeletionFailed(msg.into())
     }
+
+    pub fn in_use(msg: impl Into<String>) -> Self {
+        Self::InUse(msg.into())
+    }
 }
 
 impl FileManagerError {
@@ -0,0 +1,219 @@
+//! Crash-safe file helpers shared by the launcher and auth storage
+//!
+//! Config files are written through a uniquely named temporary sibling that
+//! is fsynced and then renamed over the target, so a crash mid-write leaves either the old or
+//! the new contents on disk. Advisory locks guard files that several
+//! `rustified` processes may touch at once.
+
+use crate::error::{Result, ResultExt};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Atomically replace the contents of `path`
+///
+/// # Errors
+///
+/// Returns an error if the temporary file cannot be written, synced or renamed into place.
+pub async fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    // Unique per write so concurrent writers never share a temporary file
+    let tmp_path = sibling_path(path, &format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+
+    let mut file = tokio::fs::File::create(&tmp_path)
+        .await
+        .with_context(|| format!("Failed to create temporary file: {}", tmp_path.display()))?;
+    file.write_all(contents.as_ref())
+        .await
+        .with_context(|| format!("Failed to write temporary file: {}", tmp_path.display()))?;
+    file.sync_all()
+        .await
+        .with_context(|| format!("Failed to sync temporary file: {}", tmp_path.display()))?;
+    drop(file);
+
+    if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(crate::error::RustifiedError::from(e)
+            .with_context(format!("Failed to replace {}", path.display())));
+    }
+
+    // Persist the rename itself; not all platforms allow opening directories
+    #[cfg(unix)]
+    if let Some(parent) = path.parent()
+        && let Ok(dir) = File::open(parent)
+    {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// An advisory lock on a file, released when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Block until an exclusive lock on `path` is held
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be opened or locked.
+    pub fn exclusive(path: &Path) -> Result<Self> {
+        let file = open_lock_file(path)?;
+        file.lock()
+            .with_context(|| format!("Failed to lock {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    /// Block until a shared lock on `path` is held
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be opened or locked.
+    pub fn shared(path: &Path) -> Result<Self> {
+        let file = open_lock_file(path)?;
+        file.lock_shared()
+            .with_context(|| format!("Failed to lock {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    /// Wait for an exclusive lock on `path` without blocking the async runtime
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be opened or locked.
+    pub async fn exclusive_async(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::exclusive(&path)).await?
+    }
+
+    /// Wait for a shared lock on `path` without blocking the async runtime
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be opened or locked.
+    pub async fn shared_async(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::shared(&path)).await?
+    }
+
+    /// Try to take an exclusive lock on `path` without blocking
+    ///
+    /// Returns `Ok(None)` if another process already holds the lock.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be opened or locking fails for another reason.
+    pub fn try_exclusive(path: &Path) -> Result<Option<Self>> {
+        let file = open_lock_file(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(crate::error::RustifiedError::from(e)
+                .with_context(format!("Failed to lock {}", path.display()))),
+        }
+    }
+
+    /// Let a child started from `cmd` keep holding the lock after this process exits
+    ///
+    /// The lock belongs to the open file, so the child inherits it along with
+    /// the descriptor and releases it only once it and its children are gone.
+    #[cfg(unix)]
+    pub fn inherit(&self, cmd: &mut std::process::Command) {
+        use std::os::unix::io::AsRawFd;
+        use std::os::unix::process::CommandExt;
+
+        let fd = self.file.as_raw_fd();
+        // SAFETY: fcntl is async-signal-safe and only changes the child's copy of the descriptor
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Open (creating if needed) the file used to hold a lock
+fn open_lock_file(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file: {}", path.display()))
+}
+
+/// Build a path next to `path` with `suffix` appended to its file name
+pub fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+
+    #[tokio::test]
+    async fn test_write_atomic_replaces_contents() {
+        let dir = test_support::temp_dir("atomic");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+
+        write_atomic(&path, "first").await.unwrap();
+        write_atomic(&path, "second").await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_try_exclusive_detects_held_lock() {
+        let dir = test_support::temp_dir("lock");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".lock");
+
+        let held = FileLock::try_exclusive(&path).unwrap();
+        assert!(held.is_some());
+        assert!(FileLock::try_exclusive(&path).unwrap().is_none());
+
+        drop(held);
+        assert!(FileLock::try_exclusive(&path).unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_inherited_lock_outlives_the_parent_guard() {
+        let dir = test_support::temp_dir("lock");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".lock");
+
+        let lock = FileLock::try_exclusive(&path).unwrap().unwrap();
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "read _"])
+            .stdin(std::process::Stdio::piped());
+        lock.inherit(&mut cmd);
+        let mut child = cmd.spawn().unwrap();
+        drop(lock);
+
+        // The child still holds the lock until it exits
+        assert!(FileLock::try_exclusive(&path).unwrap().is_none());
+        drop(child.stdin.take());
+        child.wait().unwrap();
+        assert!(FileLock::try_exclusive(&path).unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Substitution of `${variable}` placeholders in launch arguments
+//!
+//! Game, JVM and legacy `minecraftArguments` all use the same placeholders,
+//! so one [`ArgumentContext`] is built per launch and used for all of them.
+
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+/// Values of the placeholders available to one launch
+#[derive(Debug, Clone, Default)]
+pub struct ArgumentContext {
+    variables: HashMap<&'static str, String>,
+    /// Variables whose values must not be shown, such as the access token
+    secret: HashSet<&'static str>,
+}
+
+impl ArgumentContext {
+    /// Set the value of a variable, replacing any previous value
+    pub fn set(&mut self, name: &'static str, value: impl Into<String>) {
+        self.variables.insert(name, value.into());
+    }
+
+    /// Set the value of a variable that must be redacted when the command is shown
+    pub fn set_secret(&mut self, name: &'static str, value: impl Into<String>) {
+        self.set(name, value);
+        self.secret.insert(name);
+    }
+
+    /// Values of the secret variables
+    pub fn secrets(&self) -> impl Iterator<Item = String> + '_ {
+        self.secret
+            .iter()
+            .filter_map(|name| self.get(name))
+            .map(str::to_string)
+    }
+
+    /// Value of a variable, if it is known
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(String::as_str)
+    }
+
+    /// Replace every `${name}` in `template`, wherever it appears in the string
+    ///
+    /// Unknown variables are left in place with a warning, so a new variable
+    /// in a version JSON does not stop the game from launching.
+    pub fn substitute(&self, template: &str) -> String {
+        let mut resolved = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 2..start + 2 + len];
+            resolved.push_str(&rest[..start]);
+            if let Some(value) = self.get(name) {
+                resolved.push_str(value);
+            } else {
+                warn!("Unknown argument variable '${{{name}}}', passing it through unchanged");
+                resolved.push_str(&rest[start..start + 3 + len]);
+            }
+            rest = &rest[start + 3 + len..];
+        }
+
+        resolved.push_str(rest);
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute_embedded_and_unknown_variables() {
+        let mut context = ArgumentContext::default();
+        context.set("natives_directory", "/mc/natives");
+        context.set("classpath_separator", ":");
+        context.set("empty", "");
+
+        assert_eq!(
+            context.substitute("-Djava.library.path=${natives_directory}"),
+            "-Djava.library.path=/mc/natives"
+        );
+        assert_eq!(
+            context.substitute("a${classpath_separator}b${classpath_separator}c"),
+            "a:b:c"
+        );
+        assert_eq!(context.substitute("${empty}"), "");
+        assert_eq!(
+            context.substitute("--x ${not_a_variable} ${natives_directory}"),
+            "--x ${not_a_variable} /mc/natives"
+        );
+        assert_eq!(context.substitute("${unterminated"), "${unterminated");
+    }
+}
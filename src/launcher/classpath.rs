@@ -0,0 +1,217 @@
+//! Construction of the game's classpath
+//!
+//! Libraries are keyed by `group:artifact` (plus classifier). When a loader
+//! profile and the vanilla list both contain an artifact, as often happens
+//! with ASM and Guava, only the highest version is kept; if the versions
+//! cannot be ordered the one listed first wins, which is the loader's since
+//! its libraries come before the vanilla ones. Every jar must exist, a
+//! missing one fails the launch instead of surfacing later as a
+//! `NoClassDefFoundError`.
+
+use crate::error::{GameError, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::debug;
+
+use crate::launcher::maven::MavenCoordinate;
+use crate::launcher::minecraft_dir::MinecraftDir;
+use crate::launcher::version::{Library, VersionInfo};
+
+/// Libraries that replace the vanilla client jar, as patched by Forge and `NeoForge`
+const CLIENT_JAR_PROVIDERS: &[(&str, &str)] = &[
+    ("net.minecraft", "client"),
+    ("net.minecraftforge", "forge"),
+    ("net.neoforged", "neoforge"),
+];
+
+/// Entries of the classpath, in order
+///
+/// # Errors
+///
+/// Returns an error listing every jar that is missing on disk.
+pub fn build(version_info: &VersionInfo, minecraft_dir: &MinecraftDir) -> Result<Vec<PathBuf>> {
+    let libraries = version_info
+        .libraries
+        .iter()
+        .filter(|library| library.should_use())
+        .filter(|library| !library.is_native_library() && library.has_artifact())
+        .map(Library::coordinate)
+        .collect::<Result<Vec<_>>>()?;
+    let libraries = resolve_conflicts(libraries);
+
+    let mut classpath = Vec::new();
+    if libraries.iter().any(supplies_client_jar) {
+        debug!("A loader library supplies the client, leaving out the vanilla jar");
+    } else {
+        classpath.push(minecraft_dir.version_jar_path(version_info.client_jar_id()));
+    }
+    classpath.extend(
+        libraries
+            .iter()
+            .map(|coordinate| minecraft_dir.library_path(&coordinate.path())),
+    );
+
+    let missing: Vec<_> = classpath.iter().filter(|path| !path.exists()).collect();
+    if !missing.is_empty() {
+        let list = missing
+            .iter()
+            .map(|path| format!("  {}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(GameError::launch_failed(format!(
+            "{} classpath entries are missing, run 'rustified prepare {}' to download them:\n{list}",
+            missing.len(),
+            version_info.id
+        ))
+        .into());
+    }
+
+    Ok(classpath)
+}
+
+/// Keep one library per `group:artifact[:classifier]`, preserving list order
+fn resolve_conflicts(libraries: Vec<MavenCoordinate>) -> Vec<MavenCoordinate> {
+    let mut chosen: HashMap<String, usize> = HashMap::new();
+    let mut kept: Vec<MavenCoordinate> = Vec::with_capacity(libraries.len());
+
+    for library in libraries {
+        let Some(&index) = chosen.get(&library.key()) else {
+            chosen.insert(library.key(), kept.len());
+            kept.push(library);
+            continue;
+        };
+
+        let current = &kept[index];
+        if compare_versions(&library.version, &current.version) == Ordering::Greater {
+            debug!("Classpath conflict: {library} replaces {current}");
+            kept[index] = library;
+        } else {
+            debug!("Classpath conflict: keeping {current} over {library}");
+        }
+    }
+
+    kept
+}
+
+/// Whether a library is a loader's patched client that replaces the vanilla jar
+fn supplies_client_jar(library: &MavenCoordinate) -> bool {
+    library.classifier.as_deref() == Some("client")
+        && CLIENT_JAR_PROVIDERS.contains(&(library.group.as_str(), library.artifact.as_str()))
+}
+
+/// Compare dotted versions part by part, numerically where both parts are numbers
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| -> Vec<String> {
+        version
+            .split(['.', '-', '_', '+'])
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (split(a), split(b));
+
+    for (a, b) in a.iter().zip(&b) {
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            // A release sorts above its pre-releases and other qualifiers
+            (Ok(_), Err(_)) => Ordering::Greater,
+            (Err(_), Ok(_)) => Ordering::Less,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    // `2.0.1` is newer than `2.0`, while `2.0-beta` is older
+    let qualifier_or_number = |extra: Option<&String>| {
+        extra.map_or(Ordering::Equal, |part| {
+            if part.parse::<u64>().is_ok() {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        })
+    };
+    match a.len().cmp(&b.len()) {
+        Ordering::Greater => qualifier_or_number(a.get(b.len())),
+        Ordering::Less => qualifier_or_number(b.get(a.len())).reverse(),
+        Ordering::Equal => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support::{self, version_info};
+
+    fn library(name: &str) -> Library {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "downloads": { "artifact": { "sha1": "", "size": 0, "url": "" } }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_conflicts_keep_highest_version() {
+        let libraries = [
+            "org.ow2.asm:asm:9.6",
+            "com.google.guava:guava:21.0",
+            "org.ow2.asm:asm:9.3",
+            "com.google.guava:guava:31.1-jre",
+            "org.lwjgl:lwjgl:3.3.3",
+            "org.lwjgl:lwjgl:3.3.3:natives-linux",
+        ]
+        .map(|name| MavenCoordinate::parse(name).unwrap());
+        let kept: Vec<_> = resolve_conflicts(libraries.to_vec())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                "org.ow2.asm:asm:9.6",
+                "com.google.guava:guava:31.1-jre",
+                "org.lwjgl:lwjgl:3.3.3",
+                "org.lwjgl:lwjgl:3.3.3:natives-linux"
+            ]
+        );
+
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0-beta", "2.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_build_fails_on_missing_jars() {
+        let minecraft_dir = test_support::minecraft_dir("classpath");
+        let mut version_info = version_info(serde_json::json!({
+            "id": "1.20.4-forge",
+            "jar": "1.20.4"
+        }));
+        version_info.libraries.push(library("org.ow2.asm:asm:9.6"));
+
+        let error = build(&version_info, &minecraft_dir)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("2 classpath entries are missing"));
+        assert!(error.contains("1.20.4.jar"));
+
+        // A patched client from the loader replaces the vanilla jar
+        version_info
+            .libraries
+            .push(library("net.minecraftforge:forge:1.20.4-49.0.3:client"));
+        for path in [
+            "org/ow2/asm/asm/9.6/asm-9.6.jar",
+            "net/minecraftforge/forge/1.20.4-49.0.3/forge-1.20.4-49.0.3-client.jar",
+        ] {
+            let path = minecraft_dir.library_path(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        assert_eq!(build(&version_info, &minecraft_dir).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&minecraft_dir.base_path).unwrap();
+    }
+}
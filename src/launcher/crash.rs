@@ -0,0 +1,587 @@
+//! Crash report discovery and diagnosis
+//!
+//! After the game exits abnormally the newest Minecraft crash report
+//! (`crash-reports/*.txt`) or JVM fatal error log (`hs_err_pid*.log`) written
+//! during the session is parsed, and it is matched against known causes
+//! together with the launcher log of the session.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::launcher::game::GameSession;
+
+/// Number of stack trace lines kept from a crash report
+const MAX_STACK_LINES: usize = 12;
+
+/// Number of native frames of a JVM fatal error searched for the crashing library
+const MAX_NATIVE_FRAMES: usize = 5;
+
+/// Only the tail of the launcher log is searched, the cause is almost always near the end
+const LOG_TAIL_BYTES: usize = 256 * 1024;
+
+/// Where a crash report came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashReportKind {
+    /// A `crash-reports/crash-*.txt` file written by Minecraft
+    Minecraft,
+    /// An `hs_err_pid*.log` file written when the JVM itself dies
+    JvmFatalError,
+}
+
+/// The useful parts of a crash report
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub path: PathBuf,
+    pub kind: CrashReportKind,
+    /// One-line description, e.g. "Rendering overlay" or "SIGSEGV (0xb) at pc=..."
+    pub description: Option<String>,
+    /// Exception and first frames of the stack trace
+    pub stack_trace: Vec<String>,
+    /// Mods the mod loader blamed for the crash
+    pub suspected_mods: Vec<String>,
+}
+
+/// A likely cause of a crash with suggested fixes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub title: String,
+    pub explanation: String,
+    pub fixes: Vec<String>,
+}
+
+/// Everything known about why a session crashed
+#[derive(Debug, Clone)]
+pub struct CrashAnalysis {
+    pub report: Option<CrashReport>,
+    pub diagnoses: Vec<Diagnosis>,
+}
+
+/// Analyse a crashed session using its crash report and launcher log
+pub fn analyze(session: &GameSession, instance_name: &str) -> CrashAnalysis {
+    let report = session.crash_report.as_ref().and_then(|path| {
+        let content = std::fs::read_to_string(path).ok()?;
+        Some((parse_crash_report(path, &content), content))
+    });
+
+    let mut text = String::new();
+    if let Some((_, content)) = &report {
+        text.push_str(content);
+        text.push('\n');
+    }
+    if let Some(log) = read_log_tail(&session.log_path) {
+        text.push_str(&log);
+    }
+
+    let report = report.map(|(report, _)| report);
+    let mut diagnoses = diagnose(&text, session.java_version, instance_name);
+    if let Some(report) = &report
+        && !report.suspected_mods.is_empty()
+    {
+        diagnoses.push(Diagnosis {
+            title: "A mod caused the crash".to_string(),
+            explanation: format!(
+                "The mod loader suspects: {}",
+                report.suspected_mods.join(", ")
+            ),
+            fixes: vec![
+                "Update or remove the suspected mod(s)".to_string(),
+                "Check the mod's issue tracker for this crash".to_string(),
+            ],
+        });
+    }
+
+    CrashAnalysis { report, diagnoses }
+}
+
+/// The newest crash report or JVM error log written since `since`
+pub fn find_crash_report(game_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    let crash_reports = std::fs::read_dir(game_dir.join("crash-reports"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"));
+    let jvm_errors = std::fs::read_dir(game_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_jvm_error_log(path));
+
+    crash_reports
+        .chain(jvm_errors)
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            (modified >= since).then_some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+fn is_jvm_error_log(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("hs_err_pid"))
+        && path.extension().is_some_and(|ext| ext == "log")
+}
+
+/// Parse a Minecraft crash report or JVM fatal error log
+pub fn parse_crash_report(path: &Path, content: &str) -> CrashReport {
+    if is_jvm_error_log(path) || content.contains("A fatal error has been detected by the Java") {
+        parse_jvm_error_log(path, content)
+    } else {
+        parse_minecraft_report(path, content)
+    }
+}
+
+fn parse_minecraft_report(path: &Path, content: &str) -> CrashReport {
+    let lines: Vec<&str> = content.lines().collect();
+    let description_index = lines
+        .iter()
+        .position(|line| line.starts_with("Description:"));
+    let description = description_index.map(|index| {
+        lines[index]
+            .trim_start_matches("Description:")
+            .trim()
+            .to_string()
+    });
+
+    // The stack trace is the first paragraph after the description
+    let stack_trace = description_index
+        .map(|index| {
+            lines[index + 1..]
+                .iter()
+                .skip_while(|line| line.trim().is_empty())
+                .take_while(|line| !line.trim().is_empty())
+                .take(MAX_STACK_LINES)
+                .map(|line| line.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    CrashReport {
+        path: path.to_path_buf(),
+        kind: CrashReportKind::Minecraft,
+        description,
+        stack_trace,
+        suspected_mods: parse_suspected_mods(&lines),
+    }
+}
+
+/// Read the mods named after a `Suspected Mod(s):` heading
+///
+/// Forge lists them either inline or as tab-indented lines like
+/// `Create (create), Version: 0.5.1` followed by more deeply indented details.
+fn parse_suspected_mods(lines: &[&str]) -> Vec<String> {
+    let Some(index) = lines.iter().position(|line| {
+        let line = line.trim_start().to_ascii_lowercase();
+        line.starts_with("suspected mod:") || line.starts_with("suspected mods:")
+    }) else {
+        return Vec::new();
+    };
+
+    let inline = lines[index]
+        .split_once(':')
+        .map_or("", |(_, rest)| rest)
+        .trim();
+    let entries: Vec<&str> = if inline.is_empty() {
+        lines[index + 1..]
+            .iter()
+            .take_while(|line| line.starts_with('\t') || line.starts_with(' '))
+            .filter(|line| {
+                let indent = line.len() - line.trim_start().len();
+                indent == 1 || (line.starts_with(' ') && indent <= 4)
+            })
+            .map(|line| line.trim())
+            .collect()
+    } else {
+        inline.split(", ").collect()
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| entry.split(", Version").next().unwrap_or(entry).trim())
+        .filter(|entry| {
+            !entry.is_empty() && !matches!(entry.to_ascii_lowercase().as_str(), "none" | "unknown")
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_jvm_error_log(path: &Path, content: &str) -> CrashReport {
+    let header: Vec<&str> = content
+        .lines()
+        .take_while(|line| line.starts_with('#') || line.trim().is_empty())
+        .map(|line| line.trim_start_matches('#').trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    // "#  SIGSEGV (0xb) at pc=..." or "#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=..."
+    let description = header
+        .iter()
+        .find(|line| line.contains(" at pc=") || line.contains("insufficient memory"))
+        .or_else(|| header.get(1))
+        .map(|line| (*line).to_string());
+
+    let stack_trace = header
+        .iter()
+        .skip_while(|line| !line.starts_with("Problematic frame:"))
+        .skip(1)
+        .take(1)
+        .map(|line| format!("Problematic frame: {line}"))
+        .collect();
+
+    CrashReport {
+        path: path.to_path_buf(),
+        kind: CrashReportKind::JvmFatalError,
+        description,
+        stack_trace,
+        suspected_mods: Vec::new(),
+    }
+}
+
+/// Read the end of a launcher log
+fn read_log_tail(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    let start = bytes.len().saturating_sub(LOG_TAIL_BYTES);
+    Some(String::from_utf8_lossy(&bytes[start..]).into_owned())
+}
+
+/// Match crash output against known causes
+pub fn diagnose(text: &str, java_version: u32, instance_name: &str) -> Vec<Diagnosis> {
+    [
+        diagnose_wrong_java(text, java_version, instance_name),
+        diagnose_out_of_memory(text, instance_name),
+        diagnose_missing_natives(text),
+        diagnose_mixin_failure(text),
+        diagnose_graphics_driver(text),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn diagnose_wrong_java(text: &str, java_version: u32, instance_name: &str) -> Option<Diagnosis> {
+    // "compiled by a more recent version of the Java Runtime (class file version 65.0)"
+    if text.contains("UnsupportedClassVersionError") {
+        let needed = text
+            .split("class file version ")
+            .nth(1)
+            .and_then(|rest| rest.split('.').next())
+            .and_then(|version| version.parse::<u32>().ok())
+            .map(|class_version| class_version.saturating_sub(44));
+        let explanation = match needed {
+            Some(needed) => {
+                format!("The game or a mod needs Java {needed}, but it ran on Java {java_version}.")
+            }
+            None => format!("The game or a mod needs a newer Java than Java {java_version}."),
+        };
+        return Some(Diagnosis {
+            title: "Java is too old".to_string(),
+            explanation,
+            fixes: vec![
+                format!("Let the launcher pick Java: rustified instance java {instance_name} --auto"),
+                needed.map_or_else(
+                    || "Install a newer Java and run: rustified java list --refresh".to_string(),
+                    |needed| format!("Pin a suitable Java: rustified instance java {instance_name} --version {needed}"),
+                ),
+            ],
+        });
+    }
+
+    // LaunchWrapper casts the system class loader, which stopped being a URLClassLoader in Java 9
+    if text.contains("cannot be cast to class java.net.URLClassLoader")
+        || text.contains("cannot be cast to java.net.URLClassLoader")
+    {
+        return Some(Diagnosis {
+            title: "Java is too new".to_string(),
+            explanation: format!(
+                "This version uses LaunchWrapper, which only works on Java 8, but it ran on Java {java_version}."
+            ),
+            fixes: vec![format!(
+                "Pin Java 8: rustified instance java {instance_name} --version 8"
+            )],
+        });
+    }
+
+    None
+}
+
+fn diagnose_out_of_memory(text: &str, instance_name: &str) -> Option<Diagnosis> {
+    if text.contains("There is insufficient memory for the Java Runtime Environment")
+        || text.contains("Native memory allocation (mmap) failed")
+    {
+        return Some(Diagnosis {
+            title: "The computer ran out of memory".to_string(),
+            explanation: "The operating system could not give Java the memory it asked for."
+                .to_string(),
+            fixes: vec![
+                format!("Lower the allocation: rustified instance memory {instance_name} 2048"),
+                "Close other memory-hungry programs".to_string(),
+            ],
+        });
+    }
+
+    if text.contains("java.lang.OutOfMemoryError") {
+        return Some(Diagnosis {
+            title: "Minecraft ran out of memory".to_string(),
+            explanation: "The game used all of the memory it was allowed to use.".to_string(),
+            fixes: vec![
+                format!("Allow more memory: rustified instance memory {instance_name} 4096"),
+                "Lower render distance or remove heavy mods and resource packs".to_string(),
+            ],
+        });
+    }
+
+    None
+}
+
+fn diagnose_missing_natives(text: &str) -> Option<Diagnosis> {
+    let missing = text.contains("java.lang.UnsatisfiedLinkError")
+        || text.contains("in java.library.path")
+        || text.contains("Failed to locate library:");
+    missing.then(|| Diagnosis {
+        title: "Native libraries are missing".to_string(),
+        explanation: "LWJGL could not load its native libraries for this platform.".to_string(),
+        fixes: vec![
+            "Delete the version's natives folder and launch again to re-extract them".to_string(),
+            "Make sure the Java architecture matches your CPU: rustified java list".to_string(),
+        ],
+    })
+}
+
+fn diagnose_mixin_failure(text: &str) -> Option<Diagnosis> {
+    let failed = text.contains("MixinTransformerError")
+        || text.contains("InvalidMixinException")
+        || text.contains("MixinApplyError")
+        || text.contains("Mixin apply failed")
+        || text.contains("Mixin apply for mod");
+    if !failed {
+        return None;
+    }
+
+    // Fabric: "Mixin apply for mod examplemod failed examplemod.mixins.json:..."
+    // Mixin: "Mixin [examplemod.mixins.json:ExampleMixin] from mod examplemod failed ..."
+    let culprit = ["Mixin apply for mod ", "from mod "]
+        .iter()
+        .find_map(|marker| text.split(marker).nth(1))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|name| name.trim_matches(|c: char| !c.is_alphanumeric() && c != '_' && c != '-'))
+        .filter(|name| !name.is_empty());
+
+    Some(Diagnosis {
+        title: "A mod failed to apply its mixins".to_string(),
+        explanation: match culprit {
+            Some(name) => {
+                format!("The mod '{name}' is incompatible with this game version or another mod.")
+            }
+            None => "A mod is incompatible with this game version or another mod.".to_string(),
+        },
+        fixes: vec![
+            "Update the mod, or remove it to confirm it is the cause".to_string(),
+            "Check that every mod targets this Minecraft and loader version".to_string(),
+        ],
+    })
+}
+
+fn diagnose_graphics_driver(text: &str) -> Option<Diagnosis> {
+    const DRIVER_MESSAGES: &[&str] = &[
+        "GLFW error 65542",
+        "GLFW error 65543",
+        "The driver does not appear to support OpenGL",
+        "Pixel format not accelerated",
+        "No OpenGL context found",
+        "Could not create context",
+    ];
+    // Every process loads these, so they only count when the crash happened inside them
+    const DRIVER_LIBRARIES: &[&str] = &[
+        "atio6axx.dll",
+        "ig9icd64.dll",
+        "ig75icd64.dll",
+        "nvoglv64.dll",
+        "libnvidia-glcore",
+        "libGL.so",
+    ];
+
+    let frames = native_crash_frames(text);
+    let crashed_in_driver = frames.iter().any(|frame| {
+        DRIVER_LIBRARIES
+            .iter()
+            .any(|library| frame.contains(library))
+    });
+
+    (crashed_in_driver || DRIVER_MESSAGES.iter().any(|message| text.contains(message))).then(|| {
+        Diagnosis {
+            title: "Graphics driver problem".to_string(),
+            explanation: "OpenGL could not be initialised or the graphics driver crashed."
+                .to_string(),
+            fixes: vec![
+                "Update your graphics driver from the GPU vendor's website".to_string(),
+                "On laptops, make sure Java runs on the dedicated GPU".to_string(),
+            ],
+        }
+    })
+}
+
+/// The problematic frame and the top native frames of a JVM fatal error
+///
+/// Other sections such as "Dynamic libraries" list every loaded library and
+/// say nothing about where the crash happened.
+fn native_crash_frames(text: &str) -> Vec<&str> {
+    let mut frames = Vec::new();
+    let mut lines = text.lines().map(|line| line.trim_start_matches('#').trim());
+    while let Some(line) = lines.next() {
+        if line.starts_with("Problematic frame:") {
+            frames.extend(lines.next());
+        } else if line.starts_with("Native frames:") {
+            frames.extend(
+                lines
+                    .by_ref()
+                    .take_while(|frame| !frame.is_empty())
+                    .take(MAX_NATIVE_FRAMES),
+            );
+        }
+    }
+    frames
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const FORGE_REPORT: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2024-03-01 12:00:00
+Description: Rendering overlay
+
+java.lang.OutOfMemoryError: Java heap space
+\tat net.minecraft.client.renderer.Foo.bar(Foo.java:10)
+\tat net.minecraft.client.Minecraft.run(Minecraft.java:20)
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- Head --
+Thread: Render thread
+Suspected Mods:
+\tCreate (create), Version: 0.5.1
+\t\tIssue tracker URL: https://github.com/Creators-of-Create/Create/issues
+\t\tat TRANSFORMER/create@0.5.1/com.simibubi.create.Foo.bar(Foo.java:1)
+\tFlywheel (flywheel), Version: 0.6.10
+Stacktrace:
+";
+
+    const HS_ERR: &str = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  EXCEPTION_ACCESS_VIOLATION (0xc0000005) at pc=0x00007ffb1a2b3c4d, pid=1234, tid=5678
+#
+# Problematic frame:
+# C  [atio6axx.dll+0x1a2b3c]
+#
+
+---------------  S U M M A R Y ------------
+";
+
+    #[test]
+    fn test_parse_minecraft_crash_report() {
+        let report = parse_crash_report(Path::new("crash-2024-03-01.txt"), FORGE_REPORT);
+        assert_eq!(report.kind, CrashReportKind::Minecraft);
+        assert_eq!(report.description.as_deref(), Some("Rendering overlay"));
+        assert_eq!(report.stack_trace.len(), 3);
+        assert_eq!(
+            report.stack_trace[0],
+            "java.lang.OutOfMemoryError: Java heap space"
+        );
+        assert_eq!(
+            report.suspected_mods,
+            vec!["Create (create)", "Flywheel (flywheel)"]
+        );
+
+        let diagnoses = diagnose(FORGE_REPORT, 17, "modded");
+        assert_eq!(diagnoses.len(), 1);
+        assert_eq!(diagnoses[0].title, "Minecraft ran out of memory");
+    }
+
+    #[test]
+    fn test_parse_jvm_error_log() {
+        let report = parse_crash_report(Path::new("hs_err_pid1234.log"), HS_ERR);
+        assert_eq!(report.kind, CrashReportKind::JvmFatalError);
+        assert!(
+            report
+                .description
+                .as_deref()
+                .is_some_and(|d| d.starts_with("EXCEPTION_ACCESS_VIOLATION"))
+        );
+        assert_eq!(
+            report.stack_trace,
+            vec!["Problematic frame: C  [atio6axx.dll+0x1a2b3c]"]
+        );
+
+        let diagnoses = diagnose(HS_ERR, 17, "vanilla");
+        assert_eq!(diagnoses[0].title, "Graphics driver problem");
+    }
+
+    #[test]
+    fn test_loaded_driver_libraries_are_not_blamed() {
+        let hs_err = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f3a1b2c3d4e, pid=4321, tid=4322
+#
+# Problematic frame:
+# V  [libjvm.so+0x6a1b2c]  G1ParScanThreadState::trim_queue()+0x4e
+#
+
+---------------  T H R E A D  ---------------
+
+Native frames: (J=compiled Java code, j=interpreted, Vv=VM code, C=native code)
+V  [libjvm.so+0x6a1b2c]  G1ParScanThreadState::trim_queue()+0x4e
+V  [libjvm.so+0x6a2f10]  G1ParEvacuateFollowersClosure::do_void()+0x20
+C  [libpthread.so.0+0x7ea5]  start_thread+0xc5
+
+Dynamic libraries:
+7f3a10000000-7f3a10200000 r-xp 00000000 08:01 1234 /usr/lib/x86_64-linux-gnu/libGL.so.1.7.0
+7f3a12000000-7f3a14000000 r-xp 00000000 08:01 5678 /usr/lib/x86_64-linux-gnu/libnvidia-glcore.so.535.104.05
+";
+        assert!(diagnose(hs_err, 17, "vanilla").is_empty());
+
+        let in_driver = hs_err.replace(
+            "C  [libpthread.so.0+0x7ea5]",
+            "C  [libnvidia-glcore.so.535.104.05+0x1b2c3d]",
+        );
+        assert_eq!(
+            diagnose(&in_driver, 17, "vanilla")[0].title,
+            "Graphics driver problem"
+        );
+    }
+
+    #[test]
+    fn test_diagnose_known_patterns() {
+        let wrong_java = "Exception in thread \"main\" java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been compiled by a more recent version of the Java Runtime (class file version 65.0), this version of the Java Runtime only recognizes class file versions up to 61.0";
+        let diagnoses = diagnose(wrong_java, 17, "new");
+        assert_eq!(diagnoses[0].title, "Java is too old");
+        assert!(diagnoses[0].explanation.contains("Java 21"));
+
+        let launchwrapper = "java.lang.ClassCastException: class jdk.internal.loader.ClassLoaders$AppClassLoader cannot be cast to class java.net.URLClassLoader";
+        assert_eq!(
+            diagnose(launchwrapper, 17, "old")[0].title,
+            "Java is too new"
+        );
+
+        let natives = "java.lang.UnsatisfiedLinkError: no lwjgl in java.library.path";
+        assert_eq!(
+            diagnose(natives, 8, "old")[0].title,
+            "Native libraries are missing"
+        );
+
+        let mixin = "Caused by: org.spongepowered.asm.mixin.transformer.throwables.MixinTransformerError: An unexpected critical error was encountered\nCaused by: Mixin [sodium.mixins.json:FooMixin] from mod sodium failed injection check";
+        let diagnoses = diagnose(mixin, 21, "fabric");
+        assert_eq!(diagnoses[0].title, "A mod failed to apply its mixins");
+        assert!(diagnoses[0].explanation.contains("'sodium'"));
+
+        assert!(diagnose("Stopping!", 21, "clean").is_empty());
+    }
+}
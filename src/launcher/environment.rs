@@ -0,0 +1,82 @@
+//! Environment of the game process
+//!
+//! The game inherits the launcher's environment, changed by the instance's
+//! `env` entries. Variables the JVM reads options from, such as a stray
+//! `_JAVA_OPTIONS` overriding the instance's memory settings, can be removed
+//! by launching with a sanitized environment.
+
+use std::collections::BTreeMap;
+use std::process::Command;
+use tracing::{debug, warn};
+
+/// Variables the JVM picks up options or a classpath from
+pub const JVM_VARIABLES: [&str; 4] = [
+    "_JAVA_OPTIONS",
+    "JAVA_TOOL_OPTIONS",
+    "JDK_JAVA_OPTIONS",
+    "CLASSPATH",
+];
+
+/// Apply an instance's environment settings to the game command
+///
+/// `env` maps a variable to its value, or to `None` to remove it. When
+/// `sanitize` is set the [`JVM_VARIABLES`] are removed unless `env` sets them.
+pub fn apply(cmd: &mut Command, env: &BTreeMap<String, Option<String>>, sanitize: bool) {
+    for name in JVM_VARIABLES {
+        if env.contains_key(name) || std::env::var_os(name).is_none() {
+            continue;
+        }
+        if sanitize {
+            debug!("Removing {name} from the game's environment");
+            cmd.env_remove(name);
+        } else {
+            warn!(
+                "{name} is set and will be applied to the game's JVM, sanitize the instance's environment to ignore it"
+            );
+        }
+    }
+
+    for (name, value) in env {
+        match value {
+            Some(value) => cmd.env(name, value),
+            None => cmd.env_remove(name),
+        };
+    }
+}
+
+/// Whether `name` can be used as an environment variable name
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\0'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_sets_and_removes_variables() {
+        let env = BTreeMap::from([
+            (
+                "MESA_GL_VERSION_OVERRIDE".to_string(),
+                Some("4.5".to_string()),
+            ),
+            ("LD_PRELOAD".to_string(), None),
+            ("CLASSPATH".to_string(), Some("extra.jar".to_string())),
+        ]);
+        let mut cmd = Command::new("java");
+        apply(&mut cmd, &env, true);
+
+        let envs: BTreeMap<_, _> = cmd
+            .get_envs()
+            .map(|(name, value)| (name.to_string_lossy().into_owned(), value.is_some()))
+            .collect();
+        assert_eq!(envs.get("MESA_GL_VERSION_OVERRIDE"), Some(&true));
+        assert_eq!(envs.get("LD_PRELOAD"), Some(&false));
+        // The instance's own value wins over sanitizing
+        assert_eq!(envs.get("CLASSPATH"), Some(&true));
+
+        assert!(is_valid_name("__GL_THREADED_OPTIMIZATIONS"));
+        assert!(!is_valid_name("A=B"));
+        assert!(!is_valid_name(""));
+    }
+}
@@ -2,30 +2,49 @@ use crate::error::{FileManagerError, Result, ResultExt};
 use crate::launcher;
 use reqwest::Client;
 use sha1::{Digest, Sha1};
-use std::fs::File;
 use std::path::Path;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::{debug, info, warn};
-use zip::ZipArchive;
 
 use crate::launcher::minecraft_dir::MinecraftDir;
+use crate::launcher::rules;
 use crate::launcher::version::{AssetManifest, Library, VersionInfo, VersionManifest};
 
+/// Variants of a native library with their architecture suffix
+type NativeVariants<'a> = Vec<(&'a Library, Option<&'a str>)>;
+
 const VERSION_MANIFEST_URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest.json";
 const RESOURCES_BASE_URL: &str = "https://resources.download.minecraft.net";
+const JAVA_RUNTIME_MANIFEST_URL: &str = "https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+/// Environment variable overriding the Java runtime manifest URL
+const JAVA_RUNTIME_MANIFEST_ENV: &str = "RUSTIFIED_JAVA_RUNTIME_MANIFEST_URL";
 
 pub struct FileManager {
     client: Client,
+    /// Index of Mojang's managed Java runtimes, may be a `file://` URL
+    pub(super) java_runtime_manifest_url: String,
 }
 
 impl FileManager {
     pub fn new() -> Self {
+        let java_runtime_manifest_url = std::env::var(JAVA_RUNTIME_MANIFEST_ENV)
+            .unwrap_or_else(|_| JAVA_RUNTIME_MANIFEST_URL.to_string());
+
         Self {
             client: Client::new(),
+            java_runtime_manifest_url,
         }
     }
 
+    /// Use a different Java runtime manifest, e.g. a local fixture
+    #[must_use]
+    pub fn with_java_runtime_manifest_url(mut self, url: impl Into<String>) -> Self {
+        self.java_runtime_manifest_url = url.into();
+        self
+    }
+
     /// Fetch the version manifest from Mojang
     pub async fn get_version_manifest(&self) -> Result<VersionManifest> {
         info!("Fetching version manifest from {VERSION_MANIFEST_URL}");
@@ -116,16 +135,20 @@ impl FileManager {
         version_info: &VersionInfo,
         minecraft_dir: &MinecraftDir,
     ) -> Result<()> {
-        let jar_path = minecraft_dir.version_jar_path(&version_info.id);
+        let jar_path = minecraft_dir.version_jar_path(version_info.client_jar_id());
         let json_path = minecraft_dir.version_json_path(&version_info.id);
 
         // Save version JSON first
         let version_json = serde_json::to_string_pretty(version_info)
             .context("Failed to serialize version info")?;
 
-        fs::write(&json_path, version_json)
+        // Instances of the same version may be prepared by several processes at once
+        let lock_path = crate::fs_utils::sibling_path(&json_path, ".lock");
+        let json_lock = crate::fs_utils::FileLock::exclusive_async(&lock_path).await?;
+        crate::fs_utils::write_atomic(&json_path, version_json)
             .await
             .with_context(|| format!("Failed to write version JSON to {}", json_path.display()))?;
+        drop(json_lock);
 
         // Download JAR if not already present and valid
         if self
@@ -164,6 +187,7 @@ impl FileManager {
         let filtered_libraries =
             launcher::files::FileManager::filter_native_libraries_by_architecture(
                 &version_info.libraries,
+                std::env::consts::ARCH,
             );
 
         debug!(
@@ -186,9 +210,8 @@ impl FileManager {
             total_libraries += 1;
 
             if library.is_native_library() {
-                downloaded_libraries += self
-                    .download_native_library(library, version_info, minecraft_dir)
-                    .await?;
+                downloaded_libraries +=
+                    self.download_native_library(library, minecraft_dir).await?;
                 continue;
             }
 
@@ -196,9 +219,7 @@ impl FileManager {
                 .download_regular_library(library, minecraft_dir)
                 .await?;
 
-            downloaded_libraries += self
-                .download_legacy_native(library, version_info, minecraft_dir)
-                .await?;
+            downloaded_libraries += self.download_legacy_native(library, minecraft_dir).await?;
         }
 
         info!(
@@ -207,16 +228,14 @@ impl FileManager {
         Ok(())
     }
 
-    // Helper for native libraries
+    // Helper for native libraries, which are extracted when the game launches
     async fn download_native_library(
         &self,
         library: &Library,
-        version_info: &VersionInfo,
         minecraft_dir: &MinecraftDir,
     ) -> Result<u32> {
         if let Some(artifact) = &library.downloads.artifact {
-            let lib_path = get_library_path(&library.name);
-            let full_path = minecraft_dir.library_path(&lib_path);
+            let full_path = minecraft_dir.library_path(&library.coordinate()?.path());
 
             if let Some(parent) = full_path.parent() {
                 fs::create_dir_all(parent).await.with_context(|| {
@@ -232,20 +251,6 @@ impl FileManager {
                     "Native library {} already exists and is valid",
                     library.name
                 );
-                // Always check if natives need to be extracted from this library
-                let natives_dir = minecraft_dir.natives_dir(&version_info.id);
-                if launcher::files::FileManager::should_extract_natives(
-                    &full_path,
-                    &natives_dir,
-                    library,
-                )? {
-                    debug!("Extracting natives from existing JAR: {}", library.name);
-                    self.extract_natives(&full_path, &natives_dir, library)
-                        .await
-                        .with_context(|| {
-                            format!("Failed to extract natives from {}", library.name)
-                        })?;
-                }
                 Ok(0)
             } else {
                 debug!("Downloading native library: {}", library.name);
@@ -259,15 +264,6 @@ impl FileManager {
                 .await
                 .with_context(|| format!("Failed to download native library: {}", library.name))?;
 
-                // Extract natives from the JAR
-                self.extract_natives(
-                    &full_path,
-                    &minecraft_dir.natives_dir(&version_info.id),
-                    library,
-                )
-                .await
-                .with_context(|| format!("Failed to extract natives from {}", library.name))?;
-
                 Ok(1)
             }
         } else {
@@ -281,9 +277,11 @@ impl FileManager {
         library: &Library,
         minecraft_dir: &MinecraftDir,
     ) -> Result<u32> {
-        if let Some(artifact) = &library.downloads.artifact {
-            let lib_path = get_library_path(&library.name);
-            let full_path = minecraft_dir.library_path(&lib_path);
+        if let Some(mut artifact) = library.artifact_download()? {
+            let full_path = minecraft_dir.library_path(&library.coordinate()?.path());
+            if artifact.sha1.is_empty() {
+                artifact.sha1 = self.fetch_maven_sha1(&artifact.url).await;
+            }
 
             if let Some(parent) = full_path.parent() {
                 fs::create_dir_all(parent).await.with_context(|| {
@@ -313,81 +311,55 @@ impl FileManager {
         }
     }
 
-    // Helper for legacy natives
+    // Helper for legacy natives, which are extracted when the game launches
     async fn download_legacy_native(
         &self,
         library: &Library,
-        version_info: &VersionInfo,
         minecraft_dir: &MinecraftDir,
     ) -> Result<u32> {
         if let (Some(classifiers), Some(native_classifier)) = (
             &library.downloads.classifiers,
             library.get_native_classifier(),
-        ) {
-            if let Some(native_download) = classifiers.get(&native_classifier) {
-                let lib_path = get_library_path(&format!("{}:{}", library.name, native_classifier));
-                let full_path = minecraft_dir.library_path(&lib_path);
-
-                if let Some(parent) = full_path.parent() {
-                    fs::create_dir_all(parent).await.with_context(|| {
-                        format!(
-                            "Failed to create native library directory: {}",
-                            parent.display()
-                        )
-                    })?;
-                }
-
-                if self
-                    .is_file_valid(&full_path, &native_download.sha1)
-                    .await?
-                {
-                    // File exists and is valid, but still need to check if natives need extraction
-                    let natives_dir = minecraft_dir.natives_dir(&version_info.id);
-                    if launcher::files::FileManager::should_extract_natives(
-                        &full_path,
-                        &natives_dir,
-                        library,
-                    )? {
-                        debug!(
-                            "Extracting natives from existing legacy JAR: {}-{}",
-                            library.name, native_classifier
-                        );
-                        self.extract_natives(&full_path, &natives_dir, library)
-                            .await
-                            .with_context(|| {
-                                format!("Failed to extract natives from {}", library.name)
-                            })?;
-                    }
-                } else {
-                    debug!(
-                        "Downloading legacy native library: {}-{}",
-                        library.name, native_classifier
-                    );
+        ) && let Some(native_download) = classifiers.get(&native_classifier)
+        {
+            let lib_path = library
+                .coordinate()?
+                .with_classifier(&native_classifier)
+                .path();
+            let full_path = minecraft_dir.library_path(&lib_path);
 
-                    self.download_file_with_verification(
-                        &native_download.url,
-                        &full_path,
-                        &native_download.sha1,
-                        native_download.size,
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent).await.with_context(|| {
+                    format!(
+                        "Failed to create native library directory: {}",
+                        parent.display()
                     )
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Failed to download native library: {}-{}",
-                            library.name, native_classifier
-                        )
-                    })?;
-
-                    // Extract natives
-                    self.extract_natives(
-                        &full_path,
-                        &minecraft_dir.natives_dir(&version_info.id),
-                        library,
+                })?;
+            }
+
+            if !self
+                .is_file_valid(&full_path, &native_download.sha1)
+                .await?
+            {
+                debug!(
+                    "Downloading legacy native library: {}-{}",
+                    library.name, native_classifier
+                );
+
+                self.download_file_with_verification(
+                    &native_download.url,
+                    &full_path,
+                    &native_download.sha1,
+                    native_download.size,
+                )
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to download native library: {}-{}",
+                        library.name, native_classifier
                     )
-                    .await
-                    .with_context(|| format!("Failed to extract natives from {}", library.name))?;
-                    return Ok(1);
-                }
+                })?;
+                return Ok(1);
             }
         }
         Ok(0)
@@ -460,16 +432,16 @@ impl FileManager {
 
                 async move {
                     // Create asset directory if needed
-                    if let Some(parent) = asset_path.parent() {
-                        if let Err(e) = fs::create_dir_all(parent).await {
-                            return Err(crate::error::RustifiedError::FileManager(
-                                FileManagerError::filesystem_error(format!(
-                                    "Failed to create asset directory {}: {}",
-                                    parent.display(),
-                                    e
-                                )),
-                            ));
-                        }
+                    if let Some(parent) = asset_path.parent()
+                        && let Err(e) = fs::create_dir_all(parent).await
+                    {
+                        return Err(crate::error::RustifiedError::FileManager(
+                            FileManagerError::filesystem_error(format!(
+                                "Failed to create asset directory {}: {}",
+                                parent.display(),
+                                e
+                            )),
+                        ));
                     }
 
                     // Check if file already exists and is valid
@@ -519,17 +491,58 @@ impl FileManager {
         info!(
             "✓ Assets processed: {downloaded_assets} downloaded, {skipped_assets} skipped, {total_assets} total"
         );
+
         Ok(())
     }
 
-    /// Download a file with SHA1 verification
-    async fn download_file_with_verification(
+    /// Download the version's log4j config into `assets/log_configs`
+    pub async fn download_log_config(
         &self,
-        url: &str,
-        path: &Path,
-        expected_sha1: &str,
-        expected_size: u64,
+        version_info: &VersionInfo,
+        minecraft_dir: &MinecraftDir,
     ) -> Result<()> {
+        let Some(config) = version_info
+            .logging
+            .as_ref()
+            .and_then(|logging| logging.client.as_ref())
+        else {
+            return Ok(());
+        };
+
+        let config_path = minecraft_dir.log_config_path(&config.file.id);
+        if self.is_file_valid(&config_path, &config.file.sha1).await? {
+            debug!("Log config already exists and is valid");
+            return Ok(());
+        }
+
+        fs::create_dir_all(minecraft_dir.log_configs_dir())
+            .await
+            .context("Failed to create log configs directory")?;
+        info!("Downloading log config: {}", config.file.id);
+        self.download_file_with_verification(
+            &config.file.url,
+            &config_path,
+            &config.file.sha1,
+            config.file.size,
+        )
+        .await
+        .with_context(|| format!("Failed to download log config {}", config.file.id))
+    }
+
+    /// Fetch the body of `url`, reading `file://` URLs from disk
+    pub(super) async fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        if url.starts_with("file://") {
+            let path = url::Url::parse(url)
+                .ok()
+                .and_then(|parsed| parsed.to_file_path().ok())
+                .ok_or_else(|| {
+                    FileManagerError::download_failed(format!("Invalid file URL: {url}"))
+                })?;
+            return fs::read(&path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()));
+        }
+
         let response = self
             .client
             .get(url)
@@ -545,54 +558,103 @@ impl FileManager {
             .into());
         }
 
-        let mut file = fs::File::create(path)
-            .await
-            .with_context(|| format!("Failed to create file: {}", path.display()))?;
-
-        let mut hasher = Sha1::new();
-
-        // Read the response body in chunks
         let bytes = response
             .bytes()
             .await
             .with_context(|| format!("Failed to read response from {url}"))?;
 
-        file.write_all(&bytes)
-            .await
-            .with_context(|| format!("Failed to write to file: {}", path.display()))?;
+        Ok(bytes.to_vec())
+    }
 
-        hasher.update(&bytes);
+    /// Download a file with SHA1 verification
+    ///
+    /// The file is verified before it is written, and written through a
+    /// temporary sibling, so an interrupted or corrupt download never ends up
+    /// at `path`. An `expected_size` of 0 means the size is unknown.
+    pub(super) async fn download_file_with_verification(
+        &self,
+        url: &str,
+        path: &Path,
+        expected_sha1: &str,
+        expected_size: u64,
+    ) -> Result<()> {
+        let bytes = self.fetch_bytes(url).await?;
         let downloaded = bytes.len() as u64;
 
-        file.flush()
-            .await
-            .with_context(|| format!("Failed to flush file: {}", path.display()))?;
+        // Libraries from a loader's maven repository may come without checksums
+        if expected_sha1.is_empty() {
+            debug!("No checksum for {url}, skipping verification");
+        } else {
+            // Verify size
+            if expected_size != 0 && downloaded != expected_size {
+                return Err(FileManagerError::validation_failed(format!(
+                    "Size mismatch: expected {expected_size}, got {downloaded}"
+                ))
+                .into());
+            }
 
-        // Verify size
-        if downloaded != expected_size {
-            return Err(FileManagerError::validation_failed(format!(
-                "Size mismatch: expected {expected_size}, got {downloaded}"
-            ))
-            .into());
+            // Verify SHA1
+            let actual_sha1 = format!("{:x}", Sha1::digest(&bytes));
+            if !actual_sha1.eq_ignore_ascii_case(expected_sha1) {
+                return Err(FileManagerError::validation_failed(format!(
+                    "SHA1 mismatch: expected {expected_sha1}, got {actual_sha1}"
+                ))
+                .into());
+            }
         }
 
-        // Verify SHA1
-        let actual_sha1 = format!("{:x}", hasher.finalize());
-        if actual_sha1 != expected_sha1 {
-            return Err(FileManagerError::validation_failed(format!(
-                "SHA1 mismatch: expected {expected_sha1}, got {actual_sha1}"
-            ))
-            .into());
+        let tmp_path = crate::fs_utils::sibling_path(
+            path,
+            &format!(".{}.part", uuid::Uuid::new_v4().simple()),
+        );
+        let written = async {
+            let mut file = fs::File::create(&tmp_path)
+                .await
+                .with_context(|| format!("Failed to create file: {}", tmp_path.display()))?;
+            file.write_all(&bytes)
+                .await
+                .with_context(|| format!("Failed to write to file: {}", tmp_path.display()))?;
+            file.flush()
+                .await
+                .with_context(|| format!("Failed to flush file: {}", tmp_path.display()))?;
+            drop(file);
+            fs::rename(&tmp_path, path)
+                .await
+                .with_context(|| format!("Failed to move download to {}", path.display()))
+        }
+        .await;
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp_path).await;
         }
+        written
+    }
 
-        Ok(())
+    /// Checksum a Maven repository publishes next to an artifact, empty if there is none
+    async fn fetch_maven_sha1(&self, artifact_url: &str) -> String {
+        let checksum_url = format!("{artifact_url}.sha1");
+        match self.fetch_bytes(&checksum_url).await {
+            Ok(bytes) => {
+                // The file holds the hash, sometimes followed by the file name
+                let text = String::from_utf8_lossy(&bytes);
+                let sha1 = text.split_whitespace().next().unwrap_or_default();
+                if sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return sha1.to_ascii_lowercase();
+                }
+                warn!("Ignoring malformed checksum at {checksum_url}");
+            }
+            Err(e) => warn!("No checksum for {artifact_url}, it cannot be verified: {e}"),
+        }
+        String::new()
     }
 
     /// Check if a file exists and has the correct SHA1 hash
-    async fn is_file_valid(&self, path: &Path, expected_sha1: &str) -> Result<bool> {
+    pub(super) async fn is_file_valid(&self, path: &Path, expected_sha1: &str) -> Result<bool> {
         if !path.exists() {
             return Ok(false);
         }
+        if expected_sha1.is_empty() {
+            return Ok(true);
+        }
 
         let Ok(content) = fs::read(path).await else {
             return Ok(false);
@@ -605,236 +667,67 @@ impl FileManager {
         Ok(actual_sha1 == expected_sha1)
     }
 
-    /// Extract native libraries from JAR files
-    async fn extract_natives(
-        &self,
-        jar_path: &Path,
-        natives_dir: &Path,
-        library: &Library,
-    ) -> Result<()> {
-        debug!(
-            "Extracting natives from {} to {}",
-            jar_path.display(),
-            natives_dir.display()
-        );
-
-        // Create natives directory
-        fs::create_dir_all(natives_dir).await.with_context(|| {
-            format!(
-                "Failed to create natives directory: {}",
-                natives_dir.display()
-            )
-        })?;
-
-        // Open the JAR file (which is actually a ZIP file)
-        let file = File::open(jar_path)
-            .with_context(|| format!("Failed to open JAR file: {}", jar_path.display()))?;
-
-        let mut archive = ZipArchive::new(file).with_context(|| {
-            format!("Failed to read JAR as ZIP archive: {}", jar_path.display())
-        })?;
-
-        // Extract files from the archive
-        for i in 0..archive.len() {
-            let mut file = archive
-                .by_index(i)
-                .with_context(|| format!("Failed to read file at index {i} from JAR"))?;
-
-            let file_path = file.name().to_string(); // Clone the path to avoid borrow issues
-
-            // Skip directories and META-INF files
-            if file_path.ends_with('/') || file_path.starts_with("META-INF/") {
-                continue;
-            }
+    /// Keep the native libraries built for `arch`, a Rust target architecture
+    ///
+    /// Natives come in variants such as `natives-windows`, `natives-windows-x86`
+    /// and `natives-windows-arm64`, where the unsuffixed one is built for
+    /// `x86_64`. The variant for `arch` is used, and the unsuffixed one counts
+    /// as the `x86_64` variant. Builds for another architecture cannot be
+    /// loaded, so when none matches the library is left out with a warning.
+    pub(super) fn filter_native_libraries_by_architecture(
+        libraries: &[Library],
+        arch: &str,
+    ) -> Vec<Library> {
+        debug!("Filtering native libraries for architecture: {arch}");
 
-            // Check if we should exclude this file based on library extract rules
-            if let Some(extract_rules) = &library.extract {
-                if let Some(exclude_patterns) = &extract_rules.exclude {
-                    if exclude_patterns
-                        .iter()
-                        .any(|pattern| file_path.contains(pattern))
-                    {
-                        debug!("Excluding file {file_path} from extraction");
-                        continue;
-                    }
-                }
-            }
-
-            // Create the full output path
-            let output_path = natives_dir.join(&file_path);
-
-            // Create parent directories if needed
-            if let Some(parent) = output_path.parent() {
-                std::fs::create_dir_all(parent)
-                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
-            }
-
-            // Extract the file
-            let mut output_file = std::fs::File::create(&output_path)
-                .with_context(|| format!("Failed to create file: {}", output_path.display()))?;
-
-            std::io::copy(&mut file, &mut output_file)
-                .with_context(|| format!("Failed to extract file: {file_path}"))?;
-
-            debug!("Extracted: {file_path}");
-        }
-
-        debug!("✓ Native extraction completed for {}", library.name);
-        Ok(())
-    }
-
-    /// Check if natives should be extracted from a library JAR
-    fn should_extract_natives(
-        jar_path: &Path,
-        natives_dir: &Path,
-        library: &Library,
-    ) -> Result<bool> {
-        // If natives directory doesn't exist, we definitely need to extract
-        if !natives_dir.exists() {
-            return Ok(true);
-        }
-
-        // Check if the specific native files from this JAR are already extracted
-        // We do this by looking at the JAR contents and seeing if those files exist in natives_dir
-        let jar_file = std::fs::File::open(jar_path)
-            .with_context(|| format!("Failed to open JAR file: {}", jar_path.display()))?;
-
-        let mut archive = zip::ZipArchive::new(jar_file)
-            .with_context(|| format!("Failed to read ZIP archive: {}", jar_path.display()))?;
-
-        // Look for .dylib files in the JAR and check if they exist in natives_dir
-        for i in 0..archive.len() {
-            let file = archive
-                .by_index(i)
-                .with_context(|| format!("Failed to read entry {i} from JAR"))?;
-
-            let name = file.name();
-            // Skip META-INF and look for actual native libraries
-            if name.starts_with("META-INF/") || file.is_dir() {
-                continue;
-            }
-
-            if std::path::Path::new(name)
-                .extension()
-                .is_some_and(|ext| ext.eq_ignore_ascii_case("dylib"))
-                || std::path::Path::new(name)
-                    .extension()
-                    .is_some_and(|ext| ext.eq_ignore_ascii_case("so"))
-                || std::path::Path::new(name)
-                    .extension()
-                    .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
-            {
-                let target_path = natives_dir.join(name);
-                if !target_path.exists() {
-                    debug!(
-                        "Native file {} from {} not found in natives directory",
-                        name, library.name
-                    );
-                    return Ok(true);
-                }
-            }
-        }
-
-        debug!(
-            "All native files from {} are already extracted",
-            library.name
-        );
-        Ok(false)
-    }
-
-    #[allow(clippy::match_same_arms)]
-    /// Filter native libraries to prefer the best architecture match
-    fn filter_native_libraries_by_architecture(libraries: &[Library]) -> Vec<Library> {
         let mut filtered = Vec::new();
-
-        let current_arch = match std::env::consts::ARCH {
-            "aarch64" => "arm64",
-            "x86_64" => "x64",
-            _ => "x64", // Default to x64 for unknown architectures
-        };
-
-        debug!("Filtering native libraries for architecture: {current_arch}");
-        debug!("Total libraries to process: {}", libraries.len());
-
-        // Group native libraries by their base name (without architecture suffix)
-        let mut grouped_libraries: std::collections::HashMap<String, Vec<&Library>> =
-            std::collections::HashMap::new();
+        // Keyed by the classifier without its architecture, in listing order
+        let mut groups: Vec<(String, NativeVariants)> = Vec::new();
 
         for library in libraries {
-            if library.is_native_library() {
-                debug!("Found native library: {}", library.name);
-
-                // Extract base name by removing architecture-specific suffixes
-                let base_name = if library.name.contains("-arm64") {
-                    library.name.replace("-arm64", "")
-                } else if library.name.contains("-x64") {
-                    library.name.replace("-x64", "")
-                } else {
-                    // Library without specific architecture suffix
-                    library.name.clone()
-                };
-
-                debug!("  Base name: {base_name}");
-                grouped_libraries
-                    .entry(base_name)
-                    .or_default()
-                    .push(library);
-            } else {
-                // Non-native libraries are kept as-is
+            let Some((base, variant_arch)) = library
+                .is_native_library()
+                .then(|| library.name.rsplit_once(':'))
+                .flatten()
+                .map(|(name, classifier)| {
+                    let (classifier, variant_arch) = rules::split_native_arch(classifier);
+                    (format!("{name}:{classifier}"), variant_arch)
+                })
+            else {
                 filtered.push(library.clone());
-            }
-        }
-
-        debug!(
-            "Grouped {} native libraries into {} groups",
-            libraries.iter().filter(|l| l.is_native_library()).count(),
-            grouped_libraries.len()
-        );
-
-        // For each group of native libraries, prefer the architecture-specific one
-        for (base_name, group) in &grouped_libraries {
-            debug!(
-                "Processing group '{}' with {} variants:",
-                base_name,
-                group.len()
-            );
-            for lib in group {
-                debug!("  - {}", lib.name);
-            }
-
-            let mut preferred_lib: Option<&Library> = None;
-
-            // Look for current architecture-specific version first
-            for lib in group {
-                if lib.name.contains(&format!("-{current_arch}")) {
-                    debug!("  Found preferred architecture match: {}", lib.name);
-                    preferred_lib = Some(lib);
-                    break;
-                }
-            }
-
-            // If no architecture-specific version found, use the generic one (without -arm64 or -x64)
-            if preferred_lib.is_none() {
-                for lib in group {
-                    if !lib.name.contains("-arm64") && !lib.name.contains("-x64") {
-                        debug!("  Using generic version: {}", lib.name);
-                        preferred_lib = Some(lib);
-                        break;
-                    }
-                }
-            }
+                continue;
+            };
 
-            // If still no preference, just use the first one
-            if preferred_lib.is_none() && !group.is_empty() {
-                debug!("  Falling back to first option: {}", group[0].name);
-                preferred_lib = Some(group[0]);
+            match groups.iter_mut().find(|(key, _)| *key == base) {
+                Some((_, variants)) => variants.push((library, variant_arch)),
+                None => groups.push((base, vec![(library, variant_arch)])),
             }
+        }
 
-            if let Some(lib) = preferred_lib {
-                debug!("  Selected: {}", lib.name);
-                filtered.push(lib.clone());
+        for (base, variants) in &groups {
+            let chosen = variants
+                .iter()
+                .find(|(_, variant_arch)| {
+                    variant_arch.is_some_and(|variant_arch| rules::arch_matches(variant_arch, arch))
+                })
+                .or_else(|| {
+                    variants.iter().find(|(_, variant_arch)| {
+                        variant_arch.is_none() && rules::arch_matches("x86_64", arch)
+                    })
+                });
+
+            if let Some((library, _)) = chosen {
+                debug!("Selected native library {} for {arch}", library.name);
+                filtered.push((*library).clone());
             } else {
-                debug!("  No library selected for group: {base_name}");
+                let available: Vec<_> = variants
+                    .iter()
+                    .map(|(_, variant_arch)| variant_arch.unwrap_or("x86_64"))
+                    .collect();
+                warn!(
+                    "No {base} natives for {arch} (available: {}), the game may fail to load them",
+                    available.join(", ")
+                );
             }
         }
 
@@ -847,33 +740,6 @@ impl FileManager {
     }
 }
 
-/// Helper function to convert library name to file path
-/// Example: org.lwjgl:lwjgl:3.3.3 -> org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar
-/// Example: org.lwjgl:lwjgl-opengl:3.3.3:natives-macos-arm64 -> org/lwjgl/lwjgl-opengl/3.3.3/lwjgl-opengl-3.3.3-natives-macos-arm64.jar
-pub fn get_library_path(library_name: &str) -> String {
-    // Parse library name like "com.mojang:brigadier:1.0.18" or "org.lwjgl:lwjgl-freetype:3.3.3:natives-macos-arm64"
-    let parts: Vec<&str> = library_name.split(':').collect();
-    if parts.len() >= 4 {
-        // Handle classifier format (e.g., org.lwjgl:lwjgl-freetype:3.3.3:natives-macos-arm64)
-        let group = parts[0].replace('.', "/");
-        let name = parts[1];
-        let version = parts[2];
-        let classifier = parts[3];
-
-        format!("{group}/{name}/{version}/{name}-{version}-{classifier}.jar")
-    } else if parts.len() >= 3 {
-        // Standard format (e.g., com.mojang:brigadier:1.0.18)
-        let group = parts[0].replace('.', "/");
-        let name = parts[1];
-        let version = parts[2];
-
-        format!("{group}/{name}/{version}/{name}-{version}.jar")
-    } else {
-        // Fallback for malformed library names
-        format!("{}.jar", library_name.replace(':', "/"))
-    }
-}
-
 // Add these additional dependencies to Cargo.toml:
 // futures-util = "0.3"
 // sha1 = "0.10"
@@ -883,3 +749,153 @@ impl Default for FileManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+
+    fn fixture(json: &str) -> VersionInfo {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_natives_follow_architecture() {
+        let version = fixture(include_str!("../../tests/fixtures/natives-1.20.4.json"));
+        let natives = |arch: &str| -> Vec<String> {
+            FileManager::filter_native_libraries_by_architecture(&version.libraries, arch)
+                .into_iter()
+                .filter(Library::is_native_library)
+                .map(|library| {
+                    library
+                        .name
+                        .trim_start_matches("org.lwjgl:lwjgl:3.3.3:")
+                        .to_string()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            natives("x86_64"),
+            ["natives-linux", "natives-macos", "natives-windows"]
+        );
+        assert_eq!(
+            natives("aarch64"),
+            [
+                "natives-linux-arm64",
+                "natives-macos-arm64",
+                "natives-windows-arm64"
+            ]
+        );
+        // The unsuffixed x86_64 builds are never used for other architectures
+        assert_eq!(natives("arm"), ["natives-linux-arm32"]);
+        assert_eq!(natives("x86"), ["natives-windows-x86"]);
+        // The classpath library is never filtered
+        assert_eq!(
+            FileManager::filter_native_libraries_by_architecture(&version.libraries, "riscv64")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_legacy_native_classifiers() {
+        let version = fixture(include_str!("../../tests/fixtures/natives-1.12.2.json"));
+        let [lwjgl, twitch] = version.libraries.as_slice() else {
+            panic!("fixture should have two libraries");
+        };
+
+        assert_eq!(
+            lwjgl.native_classifier_for("osx", "64").as_deref(),
+            Some("natives-osx")
+        );
+        assert_eq!(
+            twitch.native_classifier_for("windows", "32").as_deref(),
+            Some("natives-windows-32")
+        );
+        assert_eq!(
+            twitch.native_classifier_for("windows", "64").as_deref(),
+            Some("natives-windows-64")
+        );
+        assert_eq!(twitch.native_classifier_for("linux", "64"), None);
+
+        let classifier = twitch.native_classifier_for("windows", "64").unwrap();
+        assert!(
+            twitch
+                .downloads
+                .classifiers
+                .as_ref()
+                .unwrap()
+                .contains_key(&classifier)
+        );
+        assert_eq!(
+            twitch
+                .coordinate()
+                .unwrap()
+                .with_classifier(&classifier)
+                .path(),
+            "tv/twitch/twitch-platform/6.5/twitch-platform-6.5-natives-windows-64.jar"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_maven_libraries_are_verified_and_repaired() {
+        let dir = test_support::temp_dir("maven-library");
+        let jar = dir.join("repo/net/fabricmc/fabric-loader/0.15.0/fabric-loader-0.15.0.jar");
+        std::fs::create_dir_all(jar.parent().unwrap()).unwrap();
+        std::fs::write(&jar, b"loader classes").unwrap();
+        let sha1 = format!("{:x}", Sha1::digest(b"loader classes"));
+        std::fs::write(
+            crate::fs_utils::sibling_path(&jar, ".sha1"),
+            format!("{sha1}  fabric-loader-0.15.0.jar\n"),
+        )
+        .unwrap();
+
+        let repository = url::Url::from_directory_path(dir.join("repo")).unwrap();
+        let library: Library = serde_json::from_value(serde_json::json!({
+            "name": "net.fabricmc:fabric-loader:0.15.0",
+            "url": repository.as_str()
+        }))
+        .unwrap();
+        let minecraft_dir = MinecraftDir {
+            base_path: dir.join("minecraft"),
+        };
+        let installed = minecraft_dir.library_path(&library.coordinate().unwrap().path());
+        std::fs::create_dir_all(installed.parent().unwrap()).unwrap();
+        std::fs::write(&installed, b"loader cl").unwrap();
+
+        // A truncated copy fails the repository's checksum and is downloaded again
+        let file_manager = FileManager::new();
+        let downloaded = file_manager
+            .download_regular_library(&library, &minecraft_dir)
+            .await
+            .unwrap();
+        assert_eq!(downloaded, 1);
+        assert_eq!(std::fs::read(&installed).unwrap(), b"loader classes");
+
+        // A checksum listed in the profile is used as is, and a bad download never lands
+        let library: Library = serde_json::from_value(serde_json::json!({
+            "name": "net.fabricmc:fabric-loader:0.15.0",
+            "url": repository.as_str(),
+            "sha1": "0000000000000000000000000000000000000000",
+            "size": 14
+        }))
+        .unwrap();
+        std::fs::remove_file(&installed).unwrap();
+        assert!(
+            file_manager
+                .download_regular_library(&library, &minecraft_dir)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            std::fs::read_dir(installed.parent().unwrap())
+                .unwrap()
+                .count(),
+            0
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,30 +1,206 @@
-use crate::error::{GameError, Result, ResultExt};
-use std::env;
-use std::process::{Command, Stdio};
-use tracing::{debug, error, info};
+use crate::error::Result;
+use crate::fs_utils::FileLock;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, error, info, warn};
 
 use crate::auth::AuthResult;
-use crate::launcher::instance::InstanceConfig;
-use crate::launcher::java::JavaManager;
+use crate::launcher::arguments::ArgumentContext;
+use crate::launcher::game_log::GameLogLevel;
+use crate::launcher::hooks::{self, HookContext};
+use crate::launcher::instance::{InstanceConfig, InstanceSettings, ServerConfig};
+use crate::launcher::java::JavaInstallation;
+use crate::launcher::jvm::JvmPreset;
+use crate::launcher::memory::{self, HeapSize};
 use crate::launcher::minecraft_dir::MinecraftDir;
-use crate::launcher::version::{ArgumentValue, ArgumentValueType, VersionInfo};
-use crate::launcher::{self, get_library_path};
+use crate::launcher::plan::LaunchPlan;
+use crate::launcher::rules::{self, LaunchFeatures, QuickPlayKind};
+use crate::launcher::version::{ArgumentValue, VersionInfo};
+use crate::launcher::{classpath, crash, environment, natives, process};
 
 pub struct GameLauncher {}
 
+/// File in the instance's logs directory where the game records Quick Play sessions
+const QUICK_PLAY_LOG_NAME: &str = "quickPlay.json";
+
+/// Window size the game uses when only one dimension is configured
+const DEFAULT_WINDOW_SIZE: (u32, u32) = (854, 480);
+
+/// Separator between classpath entries on this platform
+const CLASSPATH_SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
+
+/// Launcher name and version reported to the game
+const LAUNCHER_NAME: &str = "rustified";
+const LAUNCHER_VERSION: &str = "1.0.0";
+
+/// Outcome of a single run of the game process
+#[derive(Debug, Clone)]
+pub struct GameSession {
+    /// When the game process was started
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// When the game process exited
+    pub ended_at: chrono::DateTime<chrono::Utc>,
+    /// Process exit code (`None` if killed by a signal)
+    pub exit_code: Option<i32>,
+    /// Java executable the game ran on
+    pub java_path: PathBuf,
+    /// Major version of that Java executable
+    pub java_version: u32,
+    /// Signal that terminated the game, on unix
+    pub signal: Option<i32>,
+    /// Whether the game was stopped with Ctrl-C from the launcher
+    pub stopped_by_user: bool,
+    /// Launcher log holding the game's output
+    pub log_path: PathBuf,
+    /// Crash report or JVM error log written during the session
+    pub crash_report: Option<PathBuf>,
+}
+
+impl GameSession {
+    /// Whether the game ended with anything other than a clean exit
+    ///
+    /// Sessions the user stopped from the launcher do not count as crashes.
+    pub fn crashed(&self) -> bool {
+        self.exit_code != Some(0) && !self.stopped_by_user
+    }
+}
+
+/// How the launcher supervises the game process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LaunchMode {
+    /// Stay attached, echo and log the game's output and wait for it to exit
+    #[default]
+    Attached,
+    /// Start the game in its own session and return immediately
+    Detached,
+    /// Resolve the launch command without starting the game
+    DryRun,
+}
+
+/// World or server the game joins directly once it has started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuickPlay {
+    /// Name of a world folder in `saves`
+    Singleplayer(String),
+    Multiplayer(ServerConfig),
+}
+
+impl QuickPlay {
+    /// The default Quick Play target stored in an instance's settings
+    pub fn from_settings(settings: &InstanceSettings) -> Option<Self> {
+        settings
+            .server
+            .clone()
+            .map(Self::Multiplayer)
+            .or_else(|| settings.world.clone().map(Self::Singleplayer))
+    }
+}
+
+/// Options controlling a single launch
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub mode: LaunchMode,
+    /// Least severe game log level echoed to the terminal
+    pub game_log_level: GameLogLevel,
+    /// Quick Play target overriding the instance's default for this launch
+    pub quick_play: Option<QuickPlay>,
+    /// Window width overriding the instance's setting for this launch
+    pub width: Option<u32>,
+    /// Window height overriding the instance's setting for this launch
+    pub height: Option<u32>,
+    /// Fullscreen mode overriding the instance's setting for this launch
+    pub fullscreen: Option<bool>,
+}
+
+/// What the game arguments of one launch depend on besides the version and account
+struct GameArgumentOptions<'a> {
+    quick_play: Option<&'a QuickPlay>,
+    /// Window width and height, when a size was configured
+    resolution: Option<(u32, u32)>,
+    fullscreen: bool,
+}
+
+impl<'a> GameArgumentOptions<'a> {
+    /// Combine the instance's settings with the overrides of this launch
+    fn new(
+        quick_play: Option<&'a QuickPlay>,
+        instance: Option<&InstanceConfig>,
+        options: &LaunchOptions,
+    ) -> Self {
+        let settings = instance.map(|inst| &inst.settings);
+        let width = options.width.or(settings.and_then(|s| s.width));
+        let height = options.height.or(settings.and_then(|s| s.height));
+        let resolution = (width.is_some() || height.is_some()).then(|| {
+            (
+                width.unwrap_or(DEFAULT_WINDOW_SIZE.0),
+                height.unwrap_or(DEFAULT_WINDOW_SIZE.1),
+            )
+        });
+
+        Self {
+            quick_play,
+            resolution,
+            fullscreen: options
+                .fullscreen
+                .unwrap_or(settings.is_some_and(|s| s.fullscreen)),
+        }
+    }
+
+    /// Features that feature-gated game arguments are checked against
+    ///
+    /// Players always launch with their own account, so `is_demo_user` stays off.
+    fn features(&self) -> LaunchFeatures {
+        LaunchFeatures {
+            has_custom_resolution: self.resolution.is_some(),
+            quick_play: self.quick_play.map(|quick_play| match quick_play {
+                QuickPlay::Singleplayer(_) => QuickPlayKind::Singleplayer,
+                QuickPlay::Multiplayer(_) => QuickPlayKind::Multiplayer,
+            }),
+            ..LaunchFeatures::default()
+        }
+    }
+}
+
+/// A game left running in the background, whose exit is not tracked
+#[derive(Debug, Clone)]
+pub struct DetachedGame {
+    pub pid: u32,
+    /// When the game process was started
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// Java executable the game runs on
+    pub java_path: PathBuf,
+    /// Major version of that Java executable
+    pub java_version: u32,
+    /// Log file receiving the game's output
+    pub log_path: PathBuf,
+}
+
+/// What happened after the game was started
+#[derive(Debug, Clone)]
+pub enum LaunchOutcome {
+    /// The game ran attached and has exited
+    Finished(GameSession),
+    /// The game keeps running in the background
+    Detached(DetachedGame),
+    /// Nothing was started, this is what would have run
+    DryRun(LaunchPlan),
+}
+
 impl GameLauncher {
     /// Launch the Minecraft game with a specific instance
-    pub fn launch(
+    ///
+    /// A game started in the background takes over `instance_lock` and the
+    /// lock on its natives, so both stay locked until the game exits.
+    pub async fn launch(
         version_info: &VersionInfo,
         auth: &AuthResult,
         minecraft_dir: &MinecraftDir,
-        java_manager: &JavaManager,
+        java_installation: &JavaInstallation,
         instance: Option<&InstanceConfig>,
-    ) -> Result<()> {
+        instance_lock: Option<&FileLock>,
+        options: &LaunchOptions,
+    ) -> Result<LaunchOutcome> {
         info!("Launching Minecraft {}", version_info.id);
-
-        // Get the appropriate Java installation for this Minecraft version
-        let java_installation = java_manager.get_java_for_minecraft(&version_info.id)?;
         info!(
             "Using Java {} at {}",
             java_installation.major_version,
@@ -42,592 +218,670 @@ impl GameLauncher {
         // Build the command
         let mut cmd = Command::new(&java_installation.path);
 
-        // Add JVM arguments (with instance-specific memory settings)
-        launcher::game::GameLauncher::add_jvm_arguments(
+        let quick_play = options
+            .quick_play
+            .clone()
+            .or_else(|| instance.and_then(|inst| QuickPlay::from_settings(&inst.settings)));
+        let quick_play = Self::check_quick_play(quick_play, version_info, &game_dir);
+        let game_options = GameArgumentOptions::new(quick_play.as_ref(), instance, options);
+
+        // Every argument template of this launch is resolved against the same variables
+        let classpath = Self::build_classpath(version_info, minecraft_dir)?;
+        let natives = natives::prepare(version_info, minecraft_dir)?;
+        let context = Self::argument_context(
+            version_info,
+            auth,
+            minecraft_dir,
+            &game_dir,
+            &classpath,
+            &natives.dir,
+            &game_options,
+        );
+
+        // Add JVM arguments (with instance-specific memory settings) and the classpath
+        Self::add_jvm_arguments(
             &mut cmd,
             version_info,
+            &context,
             minecraft_dir,
             instance,
+            java_installation,
         );
 
-        // Add classpath
-        launcher::game::GameLauncher::add_classpath(&mut cmd, version_info, minecraft_dir)?;
-
         // Add main class
         cmd.arg(&version_info.main_class);
 
         // Add game arguments (with instance-specific game directory)
-        launcher::game::GameLauncher::add_game_arguments(
-            &mut cmd,
-            version_info,
-            auth,
-            minecraft_dir,
-            instance,
-        )?;
+        Self::add_game_arguments(&mut cmd, version_info, &context, &game_options);
 
         // Set working directory to the game directory
         cmd.current_dir(&game_dir);
 
-        // Configure stdio
-        cmd.stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .stdin(Stdio::inherit());
+        let settings = instance.map(|inst| &inst.settings);
+        let cmd = Self::apply_process_settings(cmd, settings);
+
+        let plan = LaunchPlan::new(&cmd, context.secrets());
+        if options.mode == LaunchMode::DryRun {
+            return Ok(LaunchOutcome::DryRun(plan));
+        }
+
+        let hook_context = HookContext {
+            instance: instance.map(|inst| inst.name.as_str()),
+            version: &version_info.id,
+            game_dir: &game_dir,
+            java_path: &java_installation.path,
+        };
+        if let Some(pre_launch) = settings.and_then(|s| s.pre_launch.as_deref()) {
+            hooks::run_pre_launch(pre_launch, &hook_context).await?;
+        }
+        let post_exit = settings.and_then(|s| s.post_exit.as_deref());
 
         info!("Starting Minecraft process...");
-        info!("Java command: {cmd:?}");
+        debug!("Java command: {}", plan.command_line());
         if let Some(_inst) = instance {
             info!("Game directory: {}", game_dir.display());
         }
 
-        // Launch the game
-        let mut child = cmd.spawn().context("Failed to start Minecraft process")?;
+        let log_path = process::new_log_path(&game_dir.join("logs"))?;
 
-        info!("Minecraft process started with PID: {}", child.id());
+        if options.mode == LaunchMode::Detached {
+            if post_exit.is_some() {
+                warn!("The post-exit hook does not run for games started in the background");
+            }
+            // The game keeps its instance and natives locked until it exits
+            let locks: Vec<_> = instance_lock.into_iter().chain([natives.lock()]).collect();
+            let game = Self::spawn_detached(cmd, log_path, &locks, java_installation)?;
+            natives::remove_stale(&natives.dir);
+            return Ok(LaunchOutcome::Detached(game));
+        }
 
-        // Wait for the process to complete
-        let status = child
-            .wait()
-            .context("Failed to wait for Minecraft process")?;
+        // Launch the game and wait for it to exit
+        let started_at = chrono::Utc::now();
+        let result = process::run_attached(cmd, &log_path, options.game_log_level).await;
+        natives::remove_stale(&natives.dir);
+        let exit = result?;
+
+        let mut session = GameSession {
+            started_at,
+            ended_at: chrono::Utc::now(),
+            exit_code: exit.code,
+            java_path: java_installation.path.clone(),
+            java_version: java_installation.major_version,
+            signal: exit.signal,
+            stopped_by_user: exit.stopped_by_user,
+            log_path,
+            crash_report: None,
+        };
+        if session.crashed() {
+            session.crash_report = crash::find_crash_report(&game_dir, started_at.into());
+        }
+        if let Some(post_exit) = post_exit {
+            hooks::run_post_exit(post_exit, &hook_context, session.exit_code).await;
+        }
 
-        if status.success() {
-            info!("Minecraft exited successfully");
+        Self::log_exit(&session);
+        Ok(LaunchOutcome::Finished(session))
+    }
+
+    /// Start the game in the background, handing it `locks`
+    fn spawn_detached(
+        cmd: Command,
+        log_path: PathBuf,
+        locks: &[&FileLock],
+        java_installation: &JavaInstallation,
+    ) -> Result<DetachedGame> {
+        let started_at = chrono::Utc::now();
+        let pid = process::spawn_detached(cmd, &log_path, locks)?;
+        info!("Minecraft is running in the background with PID: {pid}");
+
+        Ok(DetachedGame {
+            pid,
+            started_at,
+            java_path: java_installation.path.clone(),
+            java_version: java_installation.major_version,
+            log_path,
+        })
+    }
+
+    /// Report how the game exited
+    fn log_exit(session: &GameSession) {
+        if session.stopped_by_user {
+            warn!("Minecraft was stopped from the launcher");
+        } else if let Some(signal) = session.signal {
+            error!("Minecraft was killed by signal {signal}");
+        } else if session.crashed() {
+            error!("Minecraft exited with code: {:?}", session.exit_code);
         } else {
-            return Err(GameError::launch_failed(format!(
-                "Minecraft exited with code: {:?}",
-                status.code()
-            ))
-            .into());
+            info!("Minecraft exited successfully");
         }
+    }
 
-        Ok(())
+    /// Apply the instance's environment and start the command through its wrapper, if any
+    fn apply_process_settings(mut cmd: Command, settings: Option<&InstanceSettings>) -> Command {
+        if let Some(settings) = settings {
+            environment::apply(&mut cmd, &settings.env, settings.sanitize_env);
+        }
+
+        let Some(wrapper) = settings.and_then(|s| s.wrapper.as_deref()) else {
+            return cmd;
+        };
+        if let Some(wrapped) = hooks::wrap(&cmd, wrapper) {
+            info!("Starting the game through: {wrapper}");
+            wrapped
+        } else {
+            warn!("Ignoring the instance's wrapper, it is empty");
+            cmd
+        }
     }
 
     /// Add JVM arguments to the command
     fn add_jvm_arguments(
         cmd: &mut Command,
         version_info: &VersionInfo,
+        context: &ArgumentContext,
         minecraft_dir: &MinecraftDir,
         instance: Option<&InstanceConfig>,
+        java: &JavaInstallation,
     ) {
         // Use instance-specific memory settings or defaults
-        let (min_mem, max_mem) = if let Some(inst) = instance {
-            if let Some(memory) = inst.settings.memory_mb {
-                let min = format!("-Xms{}M", memory / 2); // Allocate half as minimum
-                let max = format!("-Xmx{memory}M");
-                (min, max)
-            } else {
-                ("-Xms1G".to_string(), "-Xmx2G".to_string())
-            }
-        } else {
-            ("-Xms1G".to_string(), "-Xmx2G".to_string())
-        };
+        let settings = instance.map(|inst| &inst.settings);
+        let total_memory = memory::total_memory_mb();
+        let heap = HeapSize::resolve(
+            settings.and_then(|s| s.min_memory),
+            settings.and_then(|s| s.max_memory),
+            total_memory,
+        );
+        let java_args = settings.map_or(&[][..], |s| s.java_args.as_slice());
+        let heap_arguments = memory::heap_arguments(heap, java_args);
+        heap_arguments.heap.check(total_memory, java.is_64bit);
 
         // Memory and GC arguments
-        cmd.args([
-            &min_mem,
-            &max_mem,
-            "-XX:+UseG1GC",
-            "-XX:+UnlockExperimentalVMOptions",
-            "-XX:G1NewSizePercent=20",
-            "-XX:G1ReservePercent=20",
-            "-XX:MaxGCPauseMillis=50",
-            "-XX:G1HeapRegionSize=32M",
-        ]);
+        cmd.args(heap_arguments.flags);
+        let preset = settings.map_or_else(JvmPreset::default, |s| s.jvm_preset);
+        debug!("Using JVM preset {preset:?}");
+        cmd.args(preset.flags(java.major_version));
 
         // Add custom Java arguments from instance
-        if let Some(inst) = instance {
-            for arg in &inst.settings.java_args {
-                cmd.arg(arg);
-            }
-        }
+        cmd.args(heap_arguments.others);
 
         // Add Minecraft-specific system properties
         cmd.args([
-            "-Dminecraft.launcher.brand=rustified",
-            "-Dminecraft.launcher.version=1.0.0",
+            format!("-Dminecraft.launcher.brand={LAUNCHER_NAME}"),
+            format!("-Dminecraft.launcher.version={LAUNCHER_VERSION}"),
         ]);
 
         // Add version-specific JVM arguments if present
-        if let Some(arguments) = &version_info.arguments {
-            if let Some(jvm_args) = &arguments.jvm {
-                for arg in jvm_args {
-                    launcher::game::GameLauncher::add_conditional_jvm_argument(
-                        cmd,
-                        arg,
-                        version_info,
-                        minecraft_dir,
-                        instance,
-                    );
-                }
-            }
+        let jvm_args = version_info
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.jvm.as_deref());
+        if let Some(jvm_args) = jvm_args {
+            Self::add_arguments(cmd, jvm_args, context, LaunchFeatures::default());
+        }
+
+        if let Some(logging_arg) = Self::logging_argument(version_info, minecraft_dir, instance) {
+            cmd.arg(logging_arg);
+        }
+
+        // Older versions leave the classpath to the launcher
+        if !jvm_args.is_some_and(|args| Self::uses_variable(args, "${classpath}")) {
+            cmd.arg("-cp");
+            cmd.arg(context.substitute("${classpath}"));
         }
     }
 
-    /// Add classpath to the command
-    fn add_classpath(
-        cmd: &mut Command,
+    /// The JVM argument applying the version's log4j config, if it was downloaded
+    ///
+    /// A `-Dlog4j.configurationFile` in the instance's own Java arguments wins.
+    fn logging_argument(
         version_info: &VersionInfo,
         minecraft_dir: &MinecraftDir,
-    ) -> Result<()> {
-        let mut classpath = Vec::new();
-
-        // Add main game JAR
-        let game_jar = minecraft_dir.version_jar_path(&version_info.id);
-        if game_jar.exists() {
-            debug!(
-                "Adding to classpath (main game JAR): {}",
-                game_jar.display()
+        instance: Option<&InstanceConfig>,
+    ) -> Option<String> {
+        let config = version_info.logging.as_ref()?.client.as_ref()?;
+        let user_config = instance.is_some_and(|inst| {
+            inst.settings
+                .java_args
+                .iter()
+                .any(|arg| arg.starts_with("-Dlog4j.configurationFile="))
+        });
+        if user_config {
+            debug!("Instance sets its own log4j config");
+            return None;
+        }
+
+        let config_path = minecraft_dir.log_config_path(&config.file.id);
+        if !config_path.exists() {
+            warn!(
+                "Log config {} is missing, using log4j defaults",
+                config.file.id
             );
-            classpath.push(game_jar.to_string_lossy().to_string());
-        } else {
-            error!("Main game JAR not found: {}", game_jar.display());
-            return Err(GameError::launch_failed(format!(
-                "Main game JAR not found: {}",
-                game_jar.display()
-            ))
-            .into());
+            return None;
         }
 
-        // Add library JARs
-        for library in &version_info.libraries {
-            if !library.should_use() {
-                debug!("Skipping library (rules): {}", library.name);
-                continue;
-            }
+        Some(
+            config
+                .argument
+                .replace("${path}", &config_path.to_string_lossy()),
+        )
+    }
 
-            // Check if this library is primarily a native library (e.g., name contains ":natives-")
-            // Such libraries should not have their "artifact" (which is the native jar itself) added to the classpath.
-            // Their contents are handled by java.library.path.
-            let is_explicitly_native_library = library.is_native_library();
+    /// Build the classpath from the game JAR and the libraries this platform uses
+    fn build_classpath(version_info: &VersionInfo, minecraft_dir: &MinecraftDir) -> Result<String> {
+        let classpath = classpath::build(version_info, minecraft_dir)?
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(CLASSPATH_SEPARATOR);
 
-            if let Some(_artifact_download) = &library.downloads.artifact {
-                if is_explicitly_native_library {
-                    debug!(
-                        "Skipping classpath addition for explicitly native library artifact: {}",
-                        library.name
-                    );
-                } else {
-                    // This is a non-native library with a main artifact. Add it to classpath.
-                    // Example: org.lwjgl:lwjgl:3.3.3, org.lwjgl:lwjgl-glfw:3.3.3
-                    let relative_lib_path = get_library_path(&library.name); // Use imported function
-                    let full_path = minecraft_dir.library_path(&relative_lib_path);
-
-                    if full_path.exists() {
-                        debug!("Adding to classpath: {}", full_path.display());
-                        classpath.push(full_path.to_string_lossy().to_string());
-                    } else {
-                        // This could happen if download_libraries failed or json is inconsistent
-                        tracing::warn!(
-                            "Library artifact for {} (expected at {}) not found, skipping classpath addition.",
-                            library.name,
-                            full_path.display()
-                        );
-                    }
-                }
-            } else {
-                debug!(
-                    "Library {} has no main artifact, not adding to classpath directly.",
-                    library.name
-                );
-            }
+        debug!("Final Classpath: {classpath}");
+        Ok(classpath)
+    }
+
+    /// Whether any of `args` uses `variable`, e.g. `${classpath}`
+    fn uses_variable(args: &[ArgumentValue], variable: &str) -> bool {
+        args.iter()
+            .flat_map(ArgumentValue::values)
+            .any(|value| value.contains(variable))
+    }
+
+    /// Whether any of the version's game arguments uses `variable`, e.g. `${resolution_width}`
+    fn uses_game_variable(version_info: &VersionInfo, variable: &str) -> bool {
+        version_info
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.game.as_deref())
+            .is_some_and(|game_args| Self::uses_variable(game_args, variable))
+    }
+
+    /// Whether the version's arguments include Quick Play (1.20+)
+    fn supports_quick_play(version_info: &VersionInfo) -> bool {
+        Self::uses_game_variable(version_info, "${quickPlayMultiplayer}")
+    }
 
-            // Logging for classified natives (this part is for information, doesn't add to classpath)
-            // This confirms that native parts are recognized.
-            if library.downloads.classifiers.is_some() {
-                if let Some(native_classifier) = library.get_native_classifier() {
-                    debug!(
-                        "Library {} has native classifier: {}. These are handled by java.library.path.",
-                        library.name, native_classifier
+    /// Log the Quick Play target and drop it if this version cannot honour it
+    fn check_quick_play(
+        quick_play: Option<QuickPlay>,
+        version_info: &VersionInfo,
+        game_dir: &Path,
+    ) -> Option<QuickPlay> {
+        match quick_play? {
+            QuickPlay::Multiplayer(server) => {
+                info!("Joining server {server} on launch");
+                Some(QuickPlay::Multiplayer(server))
+            }
+            QuickPlay::Singleplayer(world) => {
+                if !Self::supports_quick_play(version_info) {
+                    warn!(
+                        "Minecraft {} cannot open a world on launch (needs 1.20+), ignoring world '{world}'",
+                        version_info.id
                     );
+                    return None;
                 }
+                if !game_dir.join("saves").join(&world).is_dir() {
+                    warn!("World '{world}' does not exist in this instance's saves");
+                }
+                info!("Opening world '{world}' on launch");
+                Some(QuickPlay::Singleplayer(world))
             }
         }
+    }
 
-        if classpath.is_empty() {
-            error!("Classpath is empty! This will likely cause a NoClassDefFoundError.");
-            return Err(GameError::launch_failed(
-                "Classpath construction failed, no entries found.".to_string(),
-            )
-            .into());
+    /// Variables available to the argument templates of one launch
+    fn argument_context(
+        version_info: &VersionInfo,
+        auth: &AuthResult,
+        minecraft_dir: &MinecraftDir,
+        game_dir: &Path,
+        classpath: &str,
+        natives_dir: &Path,
+        game_options: &GameArgumentOptions<'_>,
+    ) -> ArgumentContext {
+        let mut context = ArgumentContext::default();
+        let assets_root = minecraft_dir.assets_dir().to_string_lossy().into_owned();
+
+        // Account
+        context.set("auth_player_name", auth.profile.name.as_str());
+        context.set("auth_uuid", auth.profile.id.as_str());
+        context.set_secret("auth_access_token", auth.access_token.as_str());
+        context.set_secret(
+            "auth_session",
+            format!("token:{}:{}", auth.access_token, auth.profile.id),
+        );
+        context.set("user_type", "msa");
+        context.set("user_properties", "{}");
+        context.set("clientid", ""); // Not needed for our launcher
+        context.set("auth_xuid", ""); // Not needed for our launcher
+
+        // Paths and version
+        context.set("game_directory", game_dir.to_string_lossy());
+        context.set("game_assets", assets_root.as_str());
+        context.set("assets_root", assets_root);
+        context.set("assets_index_name", version_info.assets.as_str());
+        context.set("version_name", version_info.id.as_str());
+        context.set(
+            "version_type",
+            format!("{:?}", version_info.version_type).to_lowercase(),
+        );
+        context.set("natives_directory", natives_dir.to_string_lossy());
+        context.set(
+            "library_directory",
+            minecraft_dir.libraries_dir().to_string_lossy(),
+        );
+        context.set("classpath_separator", CLASSPATH_SEPARATOR);
+        context.set("classpath", classpath);
+        context.set("launcher_name", LAUNCHER_NAME);
+        context.set("launcher_version", LAUNCHER_VERSION);
+
+        // Window and Quick Play arguments are only emitted when their feature is enabled
+        context.set(
+            "quickPlayPath",
+            game_dir
+                .join("logs")
+                .join(QUICK_PLAY_LOG_NAME)
+                .to_string_lossy(),
+        );
+        match game_options.quick_play {
+            Some(QuickPlay::Singleplayer(world)) => {
+                context.set("quickPlaySingleplayer", world.as_str());
+            }
+            Some(QuickPlay::Multiplayer(server)) => {
+                context.set("quickPlayMultiplayer", server.to_string());
+            }
+            None => {}
+        }
+        if let Some((width, height)) = game_options.resolution {
+            context.set("resolution_width", width.to_string());
+            context.set("resolution_height", height.to_string());
         }
 
-        // Join classpath with platform-specific separator
-        let separator = if cfg!(windows) { ";" } else { ":" };
-        let classpath_str = classpath.join(separator);
-
-        debug!("Final Classpath: {classpath_str}");
-        cmd.args(["-cp", &classpath_str]);
-
-        Ok(())
+        context
     }
 
     /// Add game arguments to the command
     fn add_game_arguments(
         cmd: &mut Command,
         version_info: &VersionInfo,
-        auth: &AuthResult,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) -> Result<()> {
+        context: &ArgumentContext,
+        game_options: &GameArgumentOptions<'_>,
+    ) {
+        // Versions before Quick Play take the server through --server/--port
+        if let Some(QuickPlay::Multiplayer(server)) = game_options.quick_play
+            && !Self::supports_quick_play(version_info)
+        {
+            cmd.args([
+                "--server",
+                &server.address,
+                "--port",
+                &server.port_or_default().to_string(),
+            ]);
+        }
+
         // Handle modern argument format (1.13+)
         if let Some(arguments) = &version_info.arguments {
             if let Some(game_args) = &arguments.game {
-                for arg in game_args {
-                    launcher::game::GameLauncher::add_conditional_argument(
-                        cmd,
-                        arg,
-                        version_info,
-                        auth,
-                        minecraft_dir,
-                        instance,
-                    );
-                }
+                Self::add_arguments(cmd, game_args, context, game_options.features());
             }
             // Modern versions have comprehensive arguments, so we don't need to add essential arguments
         }
         // Handle legacy argument format (pre-1.13)
         else if let Some(minecraft_arguments) = &version_info.minecraft_arguments {
-            let args = launcher::game::GameLauncher::parse_legacy_arguments(
-                minecraft_arguments,
-                auth,
-                minecraft_dir,
-                instance,
-            )?;
-            cmd.args(args);
+            cmd.args(
+                minecraft_arguments
+                    .split_whitespace()
+                    .map(|part| context.substitute(part)),
+            );
 
             // For legacy versions, add essential arguments that might be missing
-            launcher::game::GameLauncher::add_essential_arguments(
-                cmd,
-                auth,
-                minecraft_dir,
-                instance,
-            );
+            Self::add_essential_arguments(cmd, context);
         }
 
-        Ok(())
+        // Versions whose arguments lack the window options still understand them
+        if let Some((width, height)) = game_options.resolution
+            && !Self::uses_game_variable(version_info, "${resolution_width}")
+        {
+            cmd.args([
+                "--width",
+                &width.to_string(),
+                "--height",
+                &height.to_string(),
+            ]);
+        }
+        if game_options.fullscreen {
+            cmd.arg("--fullscreen");
+        }
     }
 
-    /// Add conditional argument based on rules
-    fn add_conditional_argument(
+    /// Add the arguments whose rules allow them, with their variables resolved
+    fn add_arguments(
         cmd: &mut Command,
-        arg: &ArgumentValue,
-        version_info: &VersionInfo,
-        auth: &AuthResult,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
+        args: &[ArgumentValue],
+        context: &ArgumentContext,
+        features: LaunchFeatures,
     ) {
-        match arg {
-            ArgumentValue::Simple(value) => {
-                let resolved = launcher::game::GameLauncher::resolve_argument_variables(
-                    value,
-                    version_info,
-                    auth,
-                    minecraft_dir,
-                    instance,
-                );
-                // Filter out demo argument when user has valid auth
-                if resolved != "--demo" {
-                    cmd.arg(resolved);
-                }
-            }
-            ArgumentValue::Conditional { rules, value } => {
-                // Check if rules match current environment
-                if launcher::game::GameLauncher::evaluate_rules(rules) {
-                    match value {
-                        ArgumentValueType::Single(val) => {
-                            let resolved = launcher::game::GameLauncher::resolve_argument_variables(
-                                val,
-                                version_info,
-                                auth,
-                                minecraft_dir,
-                                instance,
-                            );
-                            // Filter out demo argument when user has valid auth
-                            if resolved != "--demo" {
-                                cmd.arg(resolved);
-                            }
-                        }
-                        ArgumentValueType::Multiple(vals) => {
-                            for val in vals {
-                                let resolved =
-                                    launcher::game::GameLauncher::resolve_argument_variables(
-                                        val,
-                                        version_info,
-                                        auth,
-                                        minecraft_dir,
-                                        instance,
-                                    );
-                                // Filter out demo argument when user has valid auth
-                                if resolved != "--demo" {
-                                    cmd.arg(resolved);
-                                }
-                            }
-                        }
-                    }
-                }
+        for arg in args {
+            if arg
+                .rules()
+                .is_none_or(|rules| rules::rules_allow(rules, features))
+            {
+                cmd.args(arg.values().iter().map(|value| context.substitute(value)));
             }
         }
     }
 
-    /// Add conditional JVM argument based on rules (without auth parameters)
-    fn add_conditional_jvm_argument(
-        cmd: &mut Command,
-        arg: &ArgumentValue,
-        version_info: &VersionInfo,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) {
-        match arg {
-            ArgumentValue::Simple(value) => {
-                let resolved = launcher::game::GameLauncher::resolve_jvm_argument_variables(
-                    value,
-                    version_info,
-                    minecraft_dir,
-                    instance,
-                );
-                cmd.arg(resolved);
-            }
-            ArgumentValue::Conditional { rules, value } => {
-                // Check if rules match current environment
-                if launcher::game::GameLauncher::evaluate_rules(rules) {
-                    match value {
-                        ArgumentValueType::Single(val) => {
-                            let resolved =
-                                launcher::game::GameLauncher::resolve_jvm_argument_variables(
-                                    val,
-                                    version_info,
-                                    minecraft_dir,
-                                    instance,
-                                );
-                            cmd.arg(resolved);
-                        }
-                        ArgumentValueType::Multiple(vals) => {
-                            for val in vals {
-                                let resolved =
-                                    launcher::game::GameLauncher::resolve_jvm_argument_variables(
-                                        val,
-                                        version_info,
-                                        minecraft_dir,
-                                        instance,
-                                    );
-                                cmd.arg(resolved);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    /// Add essential arguments that might be missing
+    fn add_essential_arguments(cmd: &mut Command, context: &ArgumentContext) {
+        // These are essential for modern Minecraft
+        cmd.args(
+            [
+                "--username",
+                "${auth_player_name}",
+                "--uuid",
+                "${auth_uuid}",
+                "--accessToken",
+                "${auth_access_token}",
+                "--userType",
+                "${user_type}",
+                "--gameDir",
+                "${game_directory}",
+            ]
+            .map(|arg| context.substitute(arg)),
+        );
     }
+}
 
-    /// Parse legacy argument string
-    fn parse_legacy_arguments(
-        arguments: &str,
-        auth: &AuthResult,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) -> Result<Vec<String>> {
-        let mut args = Vec::new();
-
-        // Split by spaces but handle quoted strings
-        let parts: Vec<&str> = arguments.split_whitespace().collect();
-
-        for part in parts {
-            if part.starts_with("${") && part.ends_with('}') {
-                // Handle variable substitution
-                let var_name = &part[2..part.len() - 1];
-                let resolved = launcher::game::GameLauncher::resolve_legacy_variable(
-                    var_name,
-                    auth,
-                    minecraft_dir,
-                    instance,
-                )?;
-                args.push(resolved);
-            } else {
-                // Filter out demo argument when user has valid auth
-                if part != "--demo" {
-                    args.push(part.to_string());
-                }
-            }
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support::{self, version_info};
+
+    fn auth() -> AuthResult {
+        AuthResult {
+            access_token: "token".to_string(),
+            profile: serde_json::from_value(serde_json::json!({
+                "id": "uuid", "name": "Steve", "skins": null, "capes": null
+            }))
+            .unwrap(),
         }
-
-        Ok(args)
     }
 
-    /// Resolve argument variables
-    fn resolve_argument_variables(
-        arg: &str,
-        version_info: &VersionInfo,
-        auth: &AuthResult,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) -> std::string::String {
-        let mut resolved = arg.to_string();
-
-        // Determine game directory (instance-specific or default)
-        let game_dir = if let Some(inst) = instance {
-            minecraft_dir.base_path.join("instances").join(&inst.name)
-        } else {
-            minecraft_dir.base_path.clone()
-        };
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
 
-        // Common variable substitutions
-        resolved = resolved.replace("${game_directory}", &game_dir.to_string_lossy());
-        resolved = resolved.replace(
-            "${assets_root}",
-            &minecraft_dir.assets_dir().to_string_lossy(),
-        );
-        resolved = resolved.replace("${assets_index_name}", &version_info.assets);
-        resolved = resolved.replace("${version_name}", &version_info.id);
-        resolved = resolved.replace(
-            "${version_type}",
-            &format!("{:?}", version_info.version_type).to_lowercase(),
-        );
-        resolved = resolved.replace("${launcher_name}", "rustified");
-        resolved = resolved.replace("${launcher_version}", "1.0.0");
-        resolved = resolved.replace(
-            "${natives_directory}",
-            &minecraft_dir
-                .natives_dir(&version_info.id)
-                .to_string_lossy(),
+    #[test]
+    fn test_logging_argument_points_at_downloaded_config() {
+        let minecraft_dir = test_support::minecraft_dir("game");
+        let version_info = version_info(serde_json::json!({
+            "logging": { "client": {
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "file": { "id": "client-1.12.xml", "sha1": "", "size": 0, "url": "" },
+                "type": "log4j2-xml"
+            } }
+        }));
+
+        // Nothing is applied until the config has been downloaded
+        assert!(GameLauncher::logging_argument(&version_info, &minecraft_dir, None).is_none());
+
+        let config_path = minecraft_dir.log_config_path("client-1.12.xml");
+        std::fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        std::fs::write(&config_path, "<Configuration/>").unwrap();
+        assert_eq!(
+            GameLauncher::logging_argument(&version_info, &minecraft_dir, None).unwrap(),
+            format!("-Dlog4j.configurationFile={}", config_path.display())
         );
 
-        // Auth-related variables
-        resolved = resolved.replace("${auth_player_name}", &auth.profile.name);
-        resolved = resolved.replace("${auth_uuid}", &auth.profile.id);
-        resolved = resolved.replace("${auth_access_token}", &auth.access_token);
-        resolved = resolved.replace("${user_type}", "msa");
-
-        // Additional variables that may be present in newer versions
-        resolved = resolved.replace("${clientid}", ""); // Not needed for our launcher
-        resolved = resolved.replace("${auth_xuid}", ""); // Not needed for our launcher
-        resolved = resolved.replace("${resolution_width}", "854"); // Default resolution
-        resolved = resolved.replace("${resolution_height}", "480"); // Default resolution
-        resolved = resolved.replace("${quickPlayPath}", ""); // Empty to avoid Quick Play errors
-        resolved = resolved.replace("${quickPlaySingleplayer}", "");
-        resolved = resolved.replace("${quickPlayMultiplayer}", "");
-        resolved = resolved.replace("${quickPlayRealms}", "");
-
-        resolved
+        std::fs::remove_dir_all(&minecraft_dir.base_path).unwrap();
     }
 
-    /// Resolve JVM argument variables (without auth information)
-    fn resolve_jvm_argument_variables(
-        arg: &str,
+    fn game_args(
         version_info: &VersionInfo,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) -> std::string::String {
-        let mut resolved = arg.to_string();
-
-        // Determine game directory (instance-specific or default)
-        let game_dir = if let Some(inst) = instance {
-            minecraft_dir.base_path.join("instances").join(&inst.name)
-        } else {
-            minecraft_dir.base_path.clone()
-        };
-
-        // Common variable substitutions (no auth-related variables for JVM args)
-        resolved = resolved.replace("${game_directory}", &game_dir.to_string_lossy());
-        resolved = resolved.replace(
-            "${assets_root}",
-            &minecraft_dir.assets_dir().to_string_lossy(),
-        );
-        resolved = resolved.replace("${assets_index_name}", &version_info.assets);
-        resolved = resolved.replace("${version_name}", &version_info.id);
-        resolved = resolved.replace(
-            "${version_type}",
-            &format!("{:?}", version_info.version_type).to_lowercase(),
+        quick_play: Option<&QuickPlay>,
+        options: &LaunchOptions,
+    ) -> Vec<String> {
+        let mut cmd = Command::new("java");
+        let minecraft_dir = test_support::minecraft_dir("game");
+        let game_options = GameArgumentOptions::new(quick_play, None, options);
+        let context = GameLauncher::argument_context(
+            version_info,
+            &auth(),
+            &minecraft_dir,
+            &minecraft_dir.base_path,
+            "client.jar",
+            Path::new("natives"),
+            &game_options,
         );
-        resolved = resolved.replace("${launcher_name}", "rustified");
-        resolved = resolved.replace("${launcher_version}", "1.0.0");
-        resolved = resolved.replace(
-            "${natives_directory}",
-            &minecraft_dir
-                .natives_dir(&version_info.id)
-                .to_string_lossy(),
+        GameLauncher::add_game_arguments(&mut cmd, version_info, &context, &game_options);
+        args(&cmd)
+    }
+
+    #[test]
+    fn test_quick_play_arguments() {
+        let server = QuickPlay::Multiplayer(ServerConfig::parse("mc.example.com:25570").unwrap());
+        let defaults = LaunchOptions::default();
+
+        let modern = version_info(serde_json::json!({ "arguments": { "game": [
+            "--username", "${auth_player_name}",
+            { "rules": [{ "action": "allow", "features": { "has_quick_plays_support": true } }],
+              "value": ["--quickPlayPath", "${quickPlayPath}"] },
+            { "rules": [{ "action": "allow", "features": { "is_quick_play_singleplayer": true } }],
+              "value": ["--quickPlaySingleplayer", "${quickPlaySingleplayer}"] },
+            { "rules": [{ "action": "allow", "features": { "is_quick_play_multiplayer": true } }],
+              "value": ["--quickPlayMultiplayer", "${quickPlayMultiplayer}"] }
+        ] } }));
+        let modern_args = game_args(&modern, Some(&server), &defaults);
+        assert!(modern_args.contains(&"--quickPlayPath".to_string()));
+        assert!(!modern_args.contains(&"--quickPlaySingleplayer".to_string()));
+        let multiplayer = modern_args
+            .iter()
+            .position(|arg| arg == "--quickPlayMultiplayer")
+            .unwrap();
+        assert_eq!(modern_args[multiplayer + 1], "mc.example.com:25570");
+
+        // Without Quick Play nothing feature-gated is emitted
+        assert_eq!(
+            game_args(&modern, None, &defaults),
+            vec!["--username", "Steve"]
         );
 
-        resolved
+        // Older versions fall back to --server/--port
+        let legacy = version_info(serde_json::json!({
+            "id": "1.12.2",
+            "minecraftArguments": "--username ${auth_player_name}"
+        }));
+        let legacy_args = game_args(&legacy, Some(&server), &defaults);
+        assert_eq!(
+            legacy_args[..4],
+            ["--server", "mc.example.com", "--port", "25570"]
+        );
     }
 
-    /// Resolve legacy variable names
-    fn resolve_legacy_variable(
-        var_name: &str,
-        auth: &AuthResult,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) -> Result<String> {
-        // Determine game directory (instance-specific or default)
-        let game_dir = if let Some(inst) = instance {
-            minecraft_dir.base_path.join("instances").join(&inst.name)
-        } else {
-            minecraft_dir.base_path.clone()
+    #[test]
+    fn test_classpath_added_once() {
+        let minecraft_dir = test_support::minecraft_dir("game");
+        let java = JavaInstallation {
+            path: PathBuf::from("java"),
+            major_version: 17,
+            version: "17.0.8".to_string(),
+            vendor: None,
+            arch: None,
+            is_64bit: true,
+        };
+        let jvm_args = |version_info: &VersionInfo| {
+            let game_options = GameArgumentOptions::new(None, None, &LaunchOptions::default());
+            let context = GameLauncher::argument_context(
+                version_info,
+                &auth(),
+                &minecraft_dir,
+                &minecraft_dir.base_path,
+                "a.jar:b.jar",
+                Path::new("natives"),
+                &game_options,
+            );
+            let mut cmd = Command::new("java");
+            GameLauncher::add_jvm_arguments(
+                &mut cmd,
+                version_info,
+                &context,
+                &minecraft_dir,
+                None,
+                &java,
+            );
+            args(&cmd)
         };
 
-        match var_name {
-            "auth_player_name" => Ok(auth.profile.name.clone()),
-            "auth_uuid" => Ok(auth.profile.id.clone()),
-            "auth_access_token" => Ok(auth.access_token.clone()),
-            "user_type" => Ok("msa".to_string()),
-            "game_directory" => Ok(game_dir.to_string_lossy().to_string()),
-            "assets_root" => Ok(minecraft_dir.assets_dir().to_string_lossy().to_string()),
-            _ => {
-                Err(GameError::launch_failed(format!("Unknown legacy variable: {var_name}")).into())
-            }
-        }
+        let modern = version_info(serde_json::json!({ "arguments": { "game": [], "jvm": [
+            "-DlibraryDirectory=${library_directory}", "-cp", "${classpath}"
+        ] } }));
+        let modern_args = jvm_args(&modern);
+        assert_eq!(modern_args.iter().filter(|arg| *arg == "-cp").count(), 1);
+        assert_eq!(modern_args.last().unwrap(), "a.jar:b.jar");
+        assert!(modern_args.contains(&format!(
+            "-DlibraryDirectory={}",
+            minecraft_dir.libraries_dir().display()
+        )));
+
+        let legacy = version_info(serde_json::json!({
+            "minecraftArguments": "--username ${auth_player_name}"
+        }));
+        let legacy_args = jvm_args(&legacy);
+        assert_eq!(legacy_args[legacy_args.len() - 2..], ["-cp", "a.jar:b.jar"]);
     }
 
-    /// Add essential arguments that might be missing
-    fn add_essential_arguments(
-        cmd: &mut Command,
-        auth: &AuthResult,
-        minecraft_dir: &MinecraftDir,
-        instance: Option<&InstanceConfig>,
-    ) {
-        // Determine game directory (instance-specific or default)
-        let game_dir = if let Some(inst) = instance {
-            minecraft_dir.base_path.join("instances").join(&inst.name)
-        } else {
-            minecraft_dir.base_path.clone()
+    #[test]
+    fn test_window_arguments() {
+        let sized = LaunchOptions {
+            width: Some(1280),
+            height: Some(720),
+            ..LaunchOptions::default()
         };
 
-        // These are essential for modern Minecraft
-        cmd.args([
-            "--username",
-            &auth.profile.name,
-            "--uuid",
-            &auth.profile.id,
-            "--accessToken",
-            &auth.access_token,
-            "--userType",
-            "msa",
-            "--gameDir",
-            &game_dir.to_string_lossy(),
-        ]);
-    }
-
-    /// Evaluate rules for conditional arguments
-    fn evaluate_rules(rules: &[crate::launcher::version::Rule]) -> bool {
-        for rule in rules {
-            let matches = if let Some(os_rule) = &rule.os {
-                if let Some(name) = &os_rule.name {
-                    match name.as_str() {
-                        "windows" => env::consts::OS == "windows",
-                        "linux" => env::consts::OS == "linux",
-                        "osx" => env::consts::OS == "macos",
-                        _ => false,
-                    }
-                } else {
-                    true
-                }
-            } else {
-                true
-            };
+        let modern = version_info(serde_json::json!({ "arguments": { "game": [
+            { "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+              "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"] }
+        ] } }));
+        assert!(game_args(&modern, None, &LaunchOptions::default()).is_empty());
+        assert_eq!(
+            game_args(&modern, None, &sized),
+            vec!["--width", "1280", "--height", "720"]
+        );
 
-            if matches {
-                return rule.action == "allow";
-            }
-        }
-        false
+        // Legacy versions get the same arguments appended, plus fullscreen
+        let legacy = version_info(serde_json::json!({
+            "id": "1.8.9",
+            "minecraftArguments": "--username ${auth_player_name}"
+        }));
+        let fullscreen = LaunchOptions {
+            fullscreen: Some(true),
+            ..sized
+        };
+        let legacy_args = game_args(&legacy, None, &fullscreen);
+        assert_eq!(
+            legacy_args[legacy_args.len() - 5..],
+            ["--width", "1280", "--height", "720", "--fullscreen"]
+        );
     }
 }
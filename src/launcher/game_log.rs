@@ -0,0 +1,331 @@
+//! Parsing of the game's console output
+//!
+//! With the version's log4j config applied the game prints XML log events,
+//! older versions and some mod loaders print `[time] [thread/LEVEL]: message`
+//! lines. Both are turned into [`GameLogEvent`]s so they can be filtered by
+//! level and printed with the launcher's own formatting. Anything else (raw
+//! stack traces, native library output) is passed through untouched.
+
+use crate::logger;
+
+/// Longest unterminated XML event buffered before it is passed through as raw output
+const MAX_PENDING_XML: usize = 64 * 1024;
+
+/// Severity of a game log event, ordered from least to most severe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GameLogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl GameLogLevel {
+    /// Parse a log4j level name
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" | "WARNING" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            "FATAL" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+
+    fn tracing_level(self) -> tracing::Level {
+        match self {
+            Self::Trace => tracing::Level::TRACE,
+            Self::Debug => tracing::Level::DEBUG,
+            Self::Info => tracing::Level::INFO,
+            Self::Warn => tracing::Level::WARN,
+            Self::Error | Self::Fatal => tracing::Level::ERROR,
+        }
+    }
+}
+
+/// One structured log event from the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameLogEvent {
+    /// Time of day as printed by the game, e.g. `12:34:56`
+    pub time: String,
+    pub level: GameLogLevel,
+    pub thread: String,
+    /// Fully qualified logger name, when the output includes it
+    pub logger: Option<String>,
+    pub message: String,
+    /// Stack trace attached to the event
+    pub throwable: Option<String>,
+}
+
+impl GameLogEvent {
+    /// Render the event like the launcher's own log lines
+    pub fn format(&self) -> String {
+        let source = match &self.logger {
+            Some(logger) => {
+                let short = logger.rsplit('.').next().unwrap_or(logger);
+                format!("{}/{short}", self.thread)
+            }
+            None => self.thread.clone(),
+        };
+        let mut line = logger::format_game_line(
+            &self.time,
+            self.level.tracing_level(),
+            &source,
+            &self.message,
+        );
+        if let Some(throwable) = &self.throwable {
+            for trace_line in throwable.lines() {
+                line.push('\n');
+                line.push_str(trace_line);
+            }
+        }
+        line
+    }
+}
+
+/// A parsed line (or group of lines) of game output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameLogLine {
+    Event(GameLogEvent),
+    /// Output that is not a log event
+    Raw(String),
+}
+
+/// Incremental parser for one output stream of the game
+#[derive(Debug, Default)]
+pub struct GameLogParser {
+    /// Lines of an XML event that has not been closed yet
+    pending_xml: Option<String>,
+}
+
+impl GameLogParser {
+    /// Feed one line of output, returning a parsed line once one is complete
+    pub fn push_line(&mut self, line: &str) -> Option<GameLogLine> {
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if let Some(pending) = &mut self.pending_xml {
+            pending.push_str(line);
+            pending.push('\n');
+        } else if line.trim_start().starts_with("<log4j:Event") {
+            self.pending_xml = Some(format!("{line}\n"));
+        } else {
+            return Some(
+                parse_plain_line(line)
+                    .map_or_else(|| GameLogLine::Raw(line.to_string()), GameLogLine::Event),
+            );
+        }
+
+        if !line.contains("</log4j:Event>") {
+            // A stray start tag must not swallow the rest of the output
+            if self
+                .pending_xml
+                .as_ref()
+                .is_some_and(|pending| pending.len() > MAX_PENDING_XML)
+            {
+                return self.finish();
+            }
+            return None;
+        }
+        let xml = self.pending_xml.take().unwrap_or_default();
+        Some(parse_xml_event(&xml).map_or(GameLogLine::Raw(xml), GameLogLine::Event))
+    }
+
+    /// Flush an unterminated XML event when the stream ends
+    pub fn finish(&mut self) -> Option<GameLogLine> {
+        self.pending_xml.take().map(GameLogLine::Raw)
+    }
+}
+
+/// Parse `[12:34:56] [Render thread/INFO]: message`, optionally with `[logger]` before the colon
+fn parse_plain_line(line: &str) -> Option<GameLogEvent> {
+    let rest = line.strip_prefix('[')?;
+    let (time, rest) = rest.split_once("] [")?;
+    let (thread_level, rest) = rest.split_once(']')?;
+    let (thread, level) = thread_level.rsplit_once('/')?;
+    let level = GameLogLevel::parse(level)?;
+
+    let (logger, message) = if let Some(message) = rest.strip_prefix(": ") {
+        (None, message)
+    } else {
+        let rest = rest.strip_prefix(" [")?;
+        let (logger, message) = rest.split_once("]: ")?;
+        (Some(logger.to_string()), message)
+    };
+
+    Some(GameLogEvent {
+        time: time.to_string(),
+        level,
+        thread: thread.to_string(),
+        logger,
+        message: message.to_string(),
+        throwable: None,
+    })
+}
+
+/// Parse a complete `<log4j:Event>` element as written by log4j's `XMLLayout`
+fn parse_xml_event(xml: &str) -> Option<GameLogEvent> {
+    let start = xml.find("<log4j:Event")?;
+    let tag_end = start + xml[start..].find('>')?;
+    let tag = &xml[start..tag_end];
+
+    let level = GameLogLevel::parse(&xml_attribute(tag, "level")?)?;
+    let time = xml_attribute(tag, "timestamp")
+        .and_then(|millis| millis.parse::<i64>().ok())
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .map(|utc| {
+            utc.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+
+    Some(GameLogEvent {
+        time,
+        level,
+        thread: xml_attribute(tag, "thread").unwrap_or_default(),
+        logger: xml_attribute(tag, "logger"),
+        message: xml_element_text(xml, "log4j:Message").unwrap_or_default(),
+        throwable: xml_element_text(xml, "log4j:Throwable"),
+    })
+}
+
+/// Value of `name="..."` inside a start tag
+fn xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let marker = format!(" {name}=\"");
+    let start = tag.find(&marker)? + marker.len();
+    let end = start + tag[start..].find('"')?;
+    Some(unescape_xml(&tag[start..end]))
+}
+
+/// Text content of an element, unwrapping CDATA
+fn xml_element_text(xml: &str, element: &str) -> Option<String> {
+    let open = format!("<{element}>");
+    let close = format!("</{element}>");
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    let text = xml[start..end].trim();
+
+    Some(
+        match text
+            .strip_prefix("<![CDATA[")
+            .and_then(|text| text.strip_suffix("]]>"))
+        {
+            Some(cdata) => cdata.to_string(),
+            None => unescape_xml(text),
+        },
+    )
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xml_event_across_lines() {
+        let mut parser = GameLogParser::default();
+        let lines = [
+            r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="WARN" thread="Render thread">"#,
+            "  <log4j:Message><![CDATA[Missing <sound> & stuff]]></log4j:Message>",
+            "  <log4j:Throwable><![CDATA[java.io.IOException: boom",
+            "\tat Foo.bar(Foo.java:1)",
+            "]]></log4j:Throwable>",
+            "</log4j:Event>",
+        ];
+
+        let parsed: Vec<_> = lines
+            .iter()
+            .filter_map(|line| parser.push_line(line))
+            .collect();
+        assert_eq!(parsed.len(), 1);
+        let GameLogLine::Event(event) = &parsed[0] else {
+            panic!("expected an event, got {parsed:?}");
+        };
+        assert_eq!(event.level, GameLogLevel::Warn);
+        assert_eq!(event.thread, "Render thread");
+        assert_eq!(
+            event.logger.as_deref(),
+            Some("net.minecraft.client.Minecraft")
+        );
+        assert_eq!(event.message, "Missing <sound> & stuff");
+        assert!(
+            event
+                .throwable
+                .as_deref()
+                .unwrap()
+                .starts_with("java.io.IOException: boom")
+        );
+        assert!(parser.finish().is_none());
+    }
+
+    #[test]
+    fn test_parse_plain_lines() {
+        let mut parser = GameLogParser::default();
+
+        let vanilla = parser
+            .push_line("[12:34:56] [Render thread/INFO]: Setting user: Steve\n")
+            .unwrap();
+        assert_eq!(
+            vanilla,
+            GameLogLine::Event(GameLogEvent {
+                time: "12:34:56".to_string(),
+                level: GameLogLevel::Info,
+                thread: "Render thread".to_string(),
+                logger: None,
+                message: "Setting user: Steve".to_string(),
+                throwable: None,
+            })
+        );
+
+        let GameLogLine::Event(forge) = parser
+            .push_line("[12:34:56] [main/DEBUG] [cpw.mods.modlauncher.Launcher/MODLAUNCHER]: Found launch services")
+            .unwrap()
+        else {
+            panic!("expected an event");
+        };
+        assert_eq!(forge.level, GameLogLevel::Debug);
+        assert_eq!(
+            forge.logger.as_deref(),
+            Some("cpw.mods.modlauncher.Launcher/MODLAUNCHER")
+        );
+
+        assert_eq!(
+            parser.push_line("\tat java.base/Foo.bar(Foo.java:1)"),
+            Some(GameLogLine::Raw(
+                "\tat java.base/Foo.bar(Foo.java:1)".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unterminated_xml_is_flushed_at_the_cap() {
+        let mut parser = GameLogParser::default();
+        assert!(parser.push_line(r#"<log4j:Event level="INFO">"#).is_none());
+
+        let filler = "x".repeat(1024);
+        let flushed = (0..100).find_map(|_| parser.push_line(&filler));
+        let Some(GameLogLine::Raw(raw)) = flushed else {
+            panic!("expected the buffered event to be flushed, got {flushed:?}");
+        };
+        assert!(raw.len() > MAX_PENDING_XML);
+
+        // Output after the flush is parsed normally again
+        assert_eq!(
+            parser.push_line("plain output"),
+            Some(GameLogLine::Raw("plain output".to_string()))
+        );
+    }
+}
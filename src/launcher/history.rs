@@ -0,0 +1,209 @@
+use crate::error::{Result, ResultExt};
+use crate::fs_utils;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::warn;
+
+use crate::launcher::game::{DetachedGame, GameSession};
+
+/// Maximum number of sessions kept in an instance's launch history
+const MAX_HISTORY_ENTRIES: usize = 200;
+
+/// File name of the launch history inside an instance directory
+const HISTORY_FILE_NAME: &str = "launch_history.json";
+
+/// A single recorded game session
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LaunchRecord {
+    /// When the game process was started
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// When the game process exited, unknown for games started in the background
+    pub ended_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Process exit code (`None` if killed by a signal)
+    pub exit_code: Option<i32>,
+    /// Whether the session ended abnormally
+    pub crashed: bool,
+    /// Minecraft version that was launched
+    pub minecraft_version: String,
+    /// Java executable used
+    pub java_path: PathBuf,
+    /// Major version of the Java executable used
+    pub java_version: u32,
+    /// Whether the game was stopped with Ctrl-C from the launcher
+    #[serde(default)]
+    pub stopped_by_user: bool,
+    /// Signal that terminated the game, on unix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    /// Launcher log holding the game's output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<PathBuf>,
+    /// Crash report or JVM error log written during the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crash_report: Option<PathBuf>,
+}
+
+impl LaunchRecord {
+    /// Build a history record from a finished game session
+    pub fn from_session(session: &GameSession, minecraft_version: &str) -> Self {
+        Self {
+            started_at: session.started_at,
+            ended_at: Some(session.ended_at),
+            exit_code: session.exit_code,
+            crashed: session.crashed(),
+            minecraft_version: minecraft_version.to_string(),
+            java_path: session.java_path.clone(),
+            java_version: session.java_version,
+            stopped_by_user: session.stopped_by_user,
+            signal: session.signal,
+            log_path: Some(session.log_path.clone()),
+            crash_report: session.crash_report.clone(),
+        }
+    }
+
+    /// Build a start-only record for a game left running in the background
+    pub fn from_detached(game: &DetachedGame, minecraft_version: &str) -> Self {
+        Self {
+            started_at: game.started_at,
+            ended_at: None,
+            exit_code: None,
+            crashed: false,
+            minecraft_version: minecraft_version.to_string(),
+            java_path: game.java_path.clone(),
+            java_version: game.java_version,
+            stopped_by_user: false,
+            signal: None,
+            log_path: Some(game.log_path.clone()),
+            crash_report: None,
+        }
+    }
+
+    /// Length of the session in whole seconds, zero if its end is unknown
+    pub fn duration_secs(&self) -> u64 {
+        self.ended_at.map_or(0, |ended_at| {
+            u64::try_from((ended_at - self.started_at).num_seconds()).unwrap_or(0)
+        })
+    }
+}
+
+/// Path of the launch history file for an instance directory
+fn history_path(instance_dir: &Path) -> PathBuf {
+    instance_dir.join(HISTORY_FILE_NAME)
+}
+
+/// Load the launch history of an instance, oldest session first
+pub async fn load_history(instance_dir: &Path) -> Result<Vec<LaunchRecord>> {
+    let path = history_path(instance_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .await
+        .context("Failed to read launch history")?;
+    let history = serde_json::from_str(&content).context("Failed to parse launch history")?;
+
+    Ok(history)
+}
+
+/// Append a record to an instance's launch history, dropping the oldest entries past the cap
+///
+/// A history that cannot be parsed is moved aside to a `.bak` file rather than
+/// overwritten. Callers serialize access through the instances directory lock.
+pub async fn append_history(instance_dir: &Path, record: LaunchRecord) -> Result<()> {
+    let path = history_path(instance_dir);
+    let mut history = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .await
+            .context("Failed to read launch history")?;
+        match serde_json::from_str(&content) {
+            Ok(history) => history,
+            Err(e) => {
+                let backup_path = fs_utils::sibling_path(&path, ".bak");
+                fs::rename(&path, &backup_path)
+                    .await
+                    .context("Failed to back up unreadable launch history")?;
+                warn!(
+                    "Launch history was unreadable ({e}), moved it to {} and started a new one",
+                    backup_path.display()
+                );
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    history.push(record);
+    if history.len() > MAX_HISTORY_ENTRIES {
+        history.drain(..history.len() - MAX_HISTORY_ENTRIES);
+    }
+
+    let content =
+        serde_json::to_string_pretty(&history).context("Failed to serialize launch history")?;
+    fs_utils::write_atomic(&path, content)
+        .await
+        .context("Failed to write launch history")?;
+
+    Ok(())
+}
+
+/// Format a number of seconds as a short human-readable duration (e.g. "3h 12m")
+pub fn format_duration(total_secs: u64) -> String {
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(59), "59s");
+        assert_eq!(format_duration(61), "1m 1s");
+        assert_eq!(format_duration(3600), "1h 0m");
+        assert_eq!(format_duration(11_520), "3h 12m");
+    }
+
+    #[tokio::test]
+    async fn test_append_backs_up_corrupt_history() {
+        let dir = test_support::temp_dir("history");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(history_path(&dir), "{ not json").unwrap();
+
+        let now = chrono::Utc::now();
+        let record = LaunchRecord {
+            started_at: now,
+            ended_at: Some(now),
+            exit_code: Some(0),
+            crashed: false,
+            minecraft_version: "1.20.4".to_string(),
+            java_path: PathBuf::from("java"),
+            java_version: 17,
+            stopped_by_user: false,
+            signal: None,
+            log_path: None,
+            crash_report: None,
+        };
+        append_history(&dir, record).await.unwrap();
+
+        assert_eq!(load_history(&dir).await.unwrap().len(), 1);
+        let backup = fs_utils::sibling_path(&history_path(&dir), ".bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), "{ not json");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
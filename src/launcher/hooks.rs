@@ -0,0 +1,205 @@
+//! User commands run around the game
+//!
+//! An instance can run a shell command before the game starts (a failing one
+//! aborts the launch) and after it exits, and can prefix the game command with
+//! a wrapper such as `gamemoderun`, `prime-run`, `mangohud` or `strace -f`.
+//! Hooks run in the game directory and receive `RUSTIFIED_*` variables
+//! describing the launch.
+
+use crate::error::{GameError, Result};
+use std::path::Path;
+use std::process::Command;
+use tracing::{info, warn};
+
+/// What hooks are told about the launch through their environment
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext<'a> {
+    pub instance: Option<&'a str>,
+    pub version: &'a str,
+    pub game_dir: &'a Path,
+    pub java_path: &'a Path,
+}
+
+impl HookContext<'_> {
+    /// Environment variables describing the launch
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("RUSTIFIED_MINECRAFT_VERSION", self.version.to_string()),
+            (
+                "RUSTIFIED_GAME_DIR",
+                self.game_dir.to_string_lossy().into_owned(),
+            ),
+            (
+                "RUSTIFIED_JAVA_PATH",
+                self.java_path.to_string_lossy().into_owned(),
+            ),
+        ];
+        if let Some(instance) = self.instance {
+            env.push(("RUSTIFIED_INSTANCE", instance.to_string()));
+        }
+        env
+    }
+}
+
+/// Run the pre-launch hook
+///
+/// # Errors
+///
+/// Returns an error if the hook cannot be started or does not exit successfully.
+pub async fn run_pre_launch(command: &str, context: &HookContext<'_>) -> Result<()> {
+    info!("Running pre-launch hook: {command}");
+    let status = shell_command(command, context)
+        .status()
+        .await
+        .map_err(|e| GameError::launch_failed(format!("Failed to run pre-launch hook: {e}")))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(GameError::launch_failed(format!(
+            "Pre-launch hook '{command}' failed ({status}), not starting the game"
+        ))
+        .into())
+    }
+}
+
+/// Run the post-exit hook, only warning if it fails since the game has already run
+pub async fn run_post_exit(command: &str, context: &HookContext<'_>, exit_code: Option<i32>) {
+    info!("Running post-exit hook: {command}");
+    let mut hook = shell_command(command, context);
+    if let Some(code) = exit_code {
+        hook.env("RUSTIFIED_EXIT_CODE", code.to_string());
+    }
+
+    match hook.status().await {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("Post-exit hook '{command}' failed ({status})"),
+        Err(e) => warn!("Failed to run post-exit hook '{command}': {e}"),
+    }
+}
+
+/// Prefix `cmd` with a wrapper command line, returning `None` if it is blank
+///
+/// On unix the wrapper is run by `sh` with the game command passed as `"$@"`,
+/// so it is quoted like any shell command. Windows has no such shell, there it
+/// is split on whitespace. The working directory and environment of `cmd`
+/// carry over to the wrapper.
+pub fn wrap(cmd: &Command, wrapper: &str) -> Option<Command> {
+    let wrapper = wrapper.trim();
+    if wrapper.is_empty() {
+        return None;
+    }
+
+    let mut prefixed = if cfg!(windows) {
+        let mut words = wrapper.split_whitespace();
+        let mut prefixed = Command::new(words.next()?);
+        prefixed.args(words);
+        prefixed
+    } else {
+        // `exec` keeps the game's PID, so stopping it from the launcher still works
+        let mut prefixed = Command::new("sh");
+        prefixed
+            .arg("-c")
+            .arg(format!("exec {wrapper} \"$@\""))
+            .arg("rustified-wrapper");
+        prefixed
+    };
+    prefixed.arg(cmd.get_program()).args(cmd.get_args());
+
+    if let Some(dir) = cmd.get_current_dir() {
+        prefixed.current_dir(dir);
+    }
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => prefixed.env(key, value),
+            None => prefixed.env_remove(key),
+        };
+    }
+    Some(prefixed)
+}
+
+/// A command running `command` through the platform shell in the game directory
+fn shell_command(command: &str, context: &HookContext<'_>) -> tokio::process::Command {
+    let mut hook = if cfg!(windows) {
+        let mut hook = tokio::process::Command::new("cmd");
+        hook.args(["/C", command]);
+        hook
+    } else {
+        let mut hook = tokio::process::Command::new("sh");
+        hook.args(["-c", command]);
+        hook
+    };
+    hook.current_dir(context.game_dir).envs(context.env());
+    hook
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wrap_prefixes_command() {
+        let mut cmd = Command::new("/usr/bin/java");
+        cmd.args(["-Xmx2G", "net.minecraft.client.main.Main"])
+            .current_dir("/games/survival")
+            .env("MESA_GL_VERSION_OVERRIDE", "4.5");
+
+        let wrapped = wrap(&cmd, "  strace -f ").unwrap();
+        assert_eq!(wrapped.get_program(), "sh");
+        let args: Vec<_> = wrapped.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-c",
+                "exec strace -f \"$@\"",
+                "rustified-wrapper",
+                "/usr/bin/java",
+                "-Xmx2G",
+                "net.minecraft.client.main.Main"
+            ]
+        );
+        assert_eq!(
+            wrapped.get_current_dir(),
+            Some(Path::new("/games/survival"))
+        );
+        assert_eq!(wrapped.get_envs().count(), 1);
+
+        assert!(wrap(&cmd, "   ").is_none());
+
+        // Quoted wrapper arguments stay intact and the game's own arguments are passed through
+        let mut game = Command::new("sh");
+        game.args([
+            "-c",
+            r#"test "$GREETING/$1" = "hello world/a b""#,
+            "sh",
+            "a b",
+        ]);
+        let status = wrap(&game, r#"env "GREETING=hello world""#)
+            .unwrap()
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_pre_launch_hook_aborts() {
+        let game_dir = std::env::temp_dir();
+        let context = HookContext {
+            instance: Some("survival"),
+            version: "1.20.4",
+            game_dir: &game_dir,
+            java_path: Path::new("/usr/bin/java"),
+        };
+
+        run_pre_launch(
+            r#"test "$RUSTIFIED_INSTANCE/$RUSTIFIED_MINECRAFT_VERSION" = survival/1.20.4"#,
+            &context,
+        )
+        .await
+        .unwrap();
+        assert!(run_pre_launch("exit 3", &context).await.is_err());
+    }
+}
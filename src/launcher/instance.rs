@@ -1,57 +1,191 @@
 use crate::error::{InstanceError, Result, ResultExt};
+use crate::fs_utils::{self, FileLock};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::{debug, info, warn};
 
+use crate::launcher::history::{self, LaunchRecord};
+use crate::launcher::jvm::JvmPreset;
+use crate::launcher::memory::{MAX_MEMORY_MB, MemorySize};
 use crate::launcher::minecraft_dir::MinecraftDir;
 
 const MAX_INSTANCE_NAME_LEN: usize = 64;
 
+/// Prefix of instance directories being deleted, never a valid instance name
+const DELETED_PREFIX: &str = ".deleted-";
+
+/// Current schema version of `instance.json`
+///
+/// Bump this and append a step to `MIGRATIONS` whenever a change to
+/// `InstanceConfig` cannot be expressed through `#[serde(default)]` alone.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Migration steps, where `MIGRATIONS[n]` upgrades a config from schema `n` to `n + 1`
+const MIGRATIONS: &[fn(&mut serde_json::Map<String, serde_json::Value>)] =
+    &[migrate_v0_to_v1, migrate_v1_to_v2];
+
 /// Configuration for a Minecraft instance
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InstanceConfig {
+    /// Schema version of this configuration file
+    #[serde(default)]
+    pub schema_version: u32,
     /// Instance name
     pub name: String,
     /// Minecraft version to use
     pub version: String,
     /// Instance description
+    #[serde(default)]
     pub description: Option<String>,
+    /// Group the instance belongs to
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Free-form tags used for filtering
+    #[serde(default)]
+    pub tags: Vec<String>,
     /// Creation timestamp
     pub created: chrono::DateTime<chrono::Utc>,
     /// Last used timestamp
+    #[serde(default)]
     pub last_used: Option<chrono::DateTime<chrono::Utc>>,
+    /// Total time spent in game, in seconds
+    #[serde(default)]
+    pub playtime_secs: u64,
+    /// Number of times the instance has been launched
+    #[serde(default)]
+    pub launch_count: u32,
     /// Instance-specific settings
+    #[serde(default)]
     pub settings: InstanceSettings,
     /// Mods configuration
+    #[serde(default)]
     pub mods: ModsConfig,
 }
 
+/// An instance directory whose configuration could not be loaded
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenInstance {
+    /// Name of the instance directory
+    pub name: String,
+    /// Path to the offending `instance.json`
+    pub config_path: PathBuf,
+    /// Why the configuration could not be loaded
+    pub error: String,
+}
+
 /// Instance-specific settings
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct InstanceSettings {
     /// Java arguments (additional to default)
     pub java_args: Vec<String>,
     /// Game arguments (additional to default)
     pub game_args: Vec<String>,
-    /// Memory allocation in MB
-    pub memory_mb: Option<u32>,
+    /// Initial heap size (`-Xms`)
+    pub min_memory: Option<MemorySize>,
+    /// Maximum heap size (`-Xmx`)
+    pub max_memory: Option<MemorySize>,
     /// Enable JVM debugging
     pub debug: bool,
     /// Custom server to connect to on launch
     pub server: Option<ServerConfig>,
+    /// Singleplayer world to open on launch
+    pub world: Option<String>,
+    /// Game window width in pixels
+    pub width: Option<u32>,
+    /// Game window height in pixels
+    pub height: Option<u32>,
+    /// Start the game in fullscreen
+    pub fullscreen: bool,
+    /// Java executable to use instead of automatic selection
+    pub java_path: Option<PathBuf>,
+    /// Java major version to use instead of automatic selection
+    pub java_version: Option<u32>,
+    /// Garbage collector flag preset
+    pub jvm_preset: JvmPreset,
+    /// Shell command run before the game starts; the launch is aborted if it fails
+    pub pre_launch: Option<String>,
+    /// Shell command run after the game exits
+    pub post_exit: Option<String>,
+    /// Command the game is started through, e.g. `gamemoderun`
+    pub wrapper: Option<String>,
+    /// Environment variables set for the game, `null` removes one
+    pub env: BTreeMap<String, Option<String>>,
+    /// Remove variables the JVM reads options from, such as `_JAVA_OPTIONS`
+    pub sanitize_env: bool,
 }
 
 /// Server configuration for quick connect
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct ServerConfig {
     pub address: String,
     pub port: Option<u16>,
 }
 
+impl ServerConfig {
+    /// Default port of Minecraft servers
+    pub const DEFAULT_PORT: u16 = 25565;
+
+    /// Parse `host`, `host:port` or `[ipv6]:port`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host is empty or the port is not a number.
+    pub fn parse(server: &str) -> Result<Self> {
+        let server = server.trim();
+        let (address, port) = if let Some(rest) = server.strip_prefix('[') {
+            let (address, port) = rest.split_once(']').ok_or_else(|| {
+                InstanceError::invalid_config(format!("Invalid server address: {server}"))
+            })?;
+            (address, port.strip_prefix(':'))
+        } else {
+            match server.rsplit_once(':') {
+                // A bare IPv6 address has several colons and no port
+                Some((address, port)) if !address.contains(':') => (address, Some(port)),
+                _ => (server, None),
+            }
+        };
+
+        if address.is_empty() {
+            return Err(
+                InstanceError::invalid_config(format!("Invalid server address: {server}")).into(),
+            );
+        }
+        let port = port
+            .map(|port| {
+                port.parse::<u16>().map_err(|_| {
+                    InstanceError::invalid_config(format!("Invalid server port: {port}"))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            address: address.to_string(),
+            port,
+        })
+    }
+
+    /// Port to connect to, falling back to the default
+    pub fn port_or_default(&self) -> u16 {
+        self.port.unwrap_or(Self::DEFAULT_PORT)
+    }
+}
+
+impl std::fmt::Display for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.address.contains(':') {
+            write!(f, "[{}]:{}", self.address, self.port_or_default())
+        } else {
+            write!(f, "{}:{}", self.address, self.port_or_default())
+        }
+    }
+}
+
 /// Mods configuration for the instance
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct ModsConfig {
     /// Mod loader type (vanilla, forge, fabric, quilt)
     pub loader: ModLoader,
@@ -85,6 +219,7 @@ pub struct InstanceManager {
     minecraft_dir: MinecraftDir,
     instances_dir: PathBuf,
     instances: HashMap<String, InstanceConfig>,
+    broken_instances: Vec<BrokenInstance>,
 }
 
 impl InstanceManager {
@@ -103,6 +238,7 @@ impl InstanceManager {
             minecraft_dir,
             instances_dir,
             instances: HashMap::new(),
+            broken_instances: Vec::new(),
         };
 
         // Load existing instances
@@ -113,12 +249,23 @@ impl InstanceManager {
 
     /// Load all instances from disk
     async fn load_instances(&mut self) -> Result<()> {
+        // Loading may migrate and rewrite configs, so keep other processes out meanwhile
+        let _dir_lock = self.lock_instances_dir().await?;
+
         let mut entries = fs::read_dir(&self.instances_dir)
             .await
             .context("Failed to read instances directory")?;
 
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
+            // Leftovers of a deletion that was interrupted
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(DELETED_PREFIX)
+            {
+                continue;
+            }
             if path.is_dir() {
                 let config_path = path.join("instance.json");
                 if config_path.exists() {
@@ -133,6 +280,12 @@ impl InstanceManager {
                                 config_path.display(),
                                 e
                             );
+                            self.broken_instances.push(BrokenInstance {
+                                name: entry.file_name().to_string_lossy().to_string(),
+                                config_path,
+                                // Only keep the message itself, not the issue-tracker footer
+                                error: e.to_string().lines().next().unwrap_or_default().to_string(),
+                            });
                         }
                     }
                 }
@@ -143,15 +296,33 @@ impl InstanceManager {
         Ok(())
     }
 
-    /// Load instance configuration from file
+    /// Load instance configuration from file, migrating it to the current schema if needed
     async fn load_instance_config(&self, config_path: &Path) -> Result<InstanceConfig> {
         let content = fs::read_to_string(config_path)
             .await
             .context("Failed to read instance config")?;
 
-        let config: InstanceConfig =
+        let value: serde_json::Value =
             serde_json::from_str(&content).context("Failed to parse instance config")?;
 
+        let (config, original_version) = migrate_instance_config(value)?;
+
+        if original_version < CURRENT_SCHEMA_VERSION {
+            // Keep the untouched original around in case the migration loses something
+            let backup_path = fs_utils::sibling_path(config_path, ".bak");
+            fs_utils::write_atomic(&backup_path, &content)
+                .await
+                .context("Failed to write instance config backup")?;
+
+            Self::write_instance_config(config_path, &config).await?;
+
+            info!(
+                "Migrated instance '{}' from schema v{original_version} to v{CURRENT_SCHEMA_VERSION} (backup at {})",
+                config.name,
+                backup_path.display()
+            );
+        }
+
         Ok(config)
     }
 
@@ -167,29 +338,107 @@ impl InstanceManager {
                 .context("Failed to create instance directory")?;
         }
 
+        let _dir_lock = self.lock_instances_dir().await?;
+        Self::write_instance_config(&config_path, config).await
+    }
+
+    /// Serialize an instance configuration to the given path
+    ///
+    /// Callers are expected to hold the instances directory lock.
+    async fn write_instance_config(config_path: &Path, config: &InstanceConfig) -> Result<()> {
         let content =
             serde_json::to_string_pretty(config).context("Failed to serialize instance config")?;
 
-        fs::write(&config_path, content)
+        fs_utils::write_atomic(config_path, content)
             .await
             .context("Failed to write instance config")?;
 
         Ok(())
     }
 
+    /// Apply a change to an instance's configuration and persist it
+    ///
+    /// The config is re-read from disk under the instances directory lock so that
+    /// changes made by another launcher process in the meantime are not lost.
+    async fn update_instance<F>(&mut self, name: &str, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut InstanceConfig),
+    {
+        let Some(cached) = self.instances.get(name) else {
+            return Err(
+                InstanceError::not_found(format!("Instance '{name}' does not exist")).into(),
+            );
+        };
+        let cached = cached.clone();
+
+        let _dir_lock = self.lock_instances_dir().await?;
+        let config_path = self.get_instance_dir(name).join("instance.json");
+        let mut config = match self.load_instance_config(&config_path).await {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Failed to re-read instance '{name}' from disk, using cached config: {e}");
+                cached
+            }
+        };
+
+        update(&mut config);
+        Self::write_instance_config(&config_path, &config).await?;
+        self.instances.insert(name.to_string(), config);
+
+        Ok(())
+    }
+
+    /// Take the short-lived lock that serializes writes to the instances directory
+    async fn lock_instances_dir(&self) -> Result<FileLock> {
+        FileLock::exclusive_async(&self.instances_dir.join(".lock")).await
+    }
+
+    /// Lock an instance for exclusive use by this process
+    ///
+    /// The instance stays locked until the returned guard is dropped. The lock
+    /// is taken under the instances directory lock, so it cannot interleave
+    /// with a deletion of the instance.
+    pub async fn lock_instance(&self, name: &str) -> Result<FileLock> {
+        let _dir_lock = self.lock_instances_dir().await?;
+        if !self.get_instance_dir(name).join("instance.json").exists() {
+            return Err(
+                InstanceError::not_found(format!("Instance '{name}' does not exist")).into(),
+            );
+        }
+        self.try_lock_instance(name)
+    }
+
+    /// Take the instance lock, callers are expected to hold the instances directory lock
+    fn try_lock_instance(&self, name: &str) -> Result<FileLock> {
+        let lock_path = self.get_instance_dir(name).join(".lock");
+        FileLock::try_exclusive(&lock_path)?.ok_or_else(|| {
+            InstanceError::in_use(format!(
+                "Instance '{name}' is in use by another rustified process"
+            ))
+            .into()
+        })
+    }
+
     /// Create a new instance (with version validation)
     pub async fn create_instance(
         &mut self,
         name: String,
         version: String,
         description: Option<String>,
+        group: Option<String>,
+        tags: &[String],
         file_manager: &crate::launcher::FileManager,
     ) -> Result<()> {
         // Check if instance already exists
         if self.instances.contains_key(&name) {
-            return Err(InstanceError::already_exists(
-                format!("Instance '{name}' already exists",),
-            )
+            return Err(
+                InstanceError::already_exists(format!("Instance '{name}' already exists")).into(),
+            );
+        }
+        if self.get_broken_instance(&name).is_some() {
+            return Err(InstanceError::already_exists(format!(
+                "Instance '{name}' already exists but its configuration is broken. Fix or delete it first."
+            ))
             .into());
         }
 
@@ -215,6 +464,15 @@ impl InstanceManager {
             .into());
         }
 
+        // Validate labels up front so a bad one does not leave a half-configured instance
+        let group = match group {
+            Some(group) => normalize_labels(&[group])?.pop(),
+            None => None,
+        };
+        let mut tags = normalize_labels(tags)?;
+        tags.sort();
+        tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
         // Validate version exists in manifest
         let manifest = file_manager.get_version_manifest().await?;
         let valid_version = manifest.versions.iter().any(|v| v.id == version);
@@ -227,11 +485,16 @@ impl InstanceManager {
         }
 
         let config = InstanceConfig {
+            schema_version: CURRENT_SCHEMA_VERSION,
             name: name.clone(),
             version,
             description,
+            group,
+            tags,
             created: chrono::Utc::now(),
             last_used: None,
+            playtime_secs: 0,
+            launch_count: 0,
             settings: InstanceSettings::default(),
             mods: ModsConfig::default(),
         };
@@ -252,27 +515,68 @@ impl InstanceManager {
 
     /// Delete an instance
     pub async fn delete_instance(&mut self, name: &str) -> Result<()> {
-        if !self.instances.contains_key(name) {
+        if !self.instances.contains_key(name) && self.get_broken_instance(name).is_none() {
             return Err(
                 InstanceError::not_found(format!("Instance '{name}' does not exist")).into(),
             );
         }
 
+        // Refuse to delete an instance that is currently running, and keep it
+        // locked until it is gone so no launch can start in it meanwhile
+        let _dir_lock = self.lock_instances_dir().await?;
+        let instance_lock = self.try_lock_instance(name)?;
+
         // Remove from disk
         let instance_dir = self.get_instance_dir(name);
         if instance_dir.exists() {
-            fs::remove_dir_all(&instance_dir)
-                .await
-                .context("Failed to delete instance directory")?;
+            self.remove_instance_dir(&instance_dir, instance_lock)
+                .await?;
         }
 
         // Remove from memory
         self.instances.remove(name);
+        self.broken_instances.retain(|b| b.name != name);
 
         info!("Deleted instance: {name}");
         Ok(())
     }
 
+    /// Remove a locked instance's directory, releasing the lock once it is gone
+    #[cfg(unix)]
+    async fn remove_instance_dir(
+        &self,
+        instance_dir: &Path,
+        instance_lock: FileLock,
+    ) -> Result<()> {
+        fs::remove_dir_all(instance_dir)
+            .await
+            .context("Failed to delete instance directory")?;
+        drop(instance_lock);
+        Ok(())
+    }
+
+    /// Remove a locked instance's directory, releasing the lock once it is gone
+    ///
+    /// Windows cannot delete the open lock file, so the directory is moved
+    /// aside while locked and removed after the lock is released.
+    #[cfg(not(unix))]
+    async fn remove_instance_dir(
+        &self,
+        instance_dir: &Path,
+        instance_lock: FileLock,
+    ) -> Result<()> {
+        let trash = self
+            .instances_dir
+            .join(format!("{DELETED_PREFIX}{}", uuid::Uuid::new_v4().simple()));
+        fs::rename(instance_dir, &trash)
+            .await
+            .context("Failed to delete instance directory")?;
+        drop(instance_lock);
+        fs::remove_dir_all(&trash)
+            .await
+            .context("Failed to delete instance directory")
+    }
+
     /// Get an instance configuration
     pub fn get_instance(&self, name: &str) -> Option<&InstanceConfig> {
         self.instances.get(name)
@@ -283,50 +587,188 @@ impl InstanceManager {
         self.instances.values().collect()
     }
 
+    /// List instance directories whose configuration failed to load
+    pub fn list_broken_instances(&self) -> &[BrokenInstance] {
+        &self.broken_instances
+    }
+
+    /// Get a broken instance by directory name
+    pub fn get_broken_instance(&self, name: &str) -> Option<&BrokenInstance> {
+        self.broken_instances.iter().find(|b| b.name == name)
+    }
+
     /// Update an instance's last used timestamp
     pub async fn update_last_used(&mut self, name: &str) -> Result<()> {
-        if let Some(config) = self.instances.get_mut(name) {
-            config.last_used = Some(chrono::Utc::now());
-            // Clone the config to avoid borrow checker issues
-            let config_clone = config.clone();
-            self.save_instance_config(&config_clone).await?;
+        if self.instances.contains_key(name) {
+            self.update_instance(name, |config| {
+                config.last_used = Some(chrono::Utc::now());
+            })
+            .await?;
         }
         Ok(())
     }
 
-    /// Get the directory path for an instance
-    pub fn get_instance_dir(&self, name: &str) -> PathBuf {
-        self.instances_dir.join(name)
-    }
+    /// Record a launch in the instance's history and add its length to the playtime
+    pub async fn record_launch(&mut self, name: &str, record: LaunchRecord) -> Result<()> {
+        let duration = record.duration_secs();
 
-    /// Set instance memory allocation
-    pub async fn set_instance_memory(&mut self, name: &str, memory_mb: u32) -> Result<()> {
-        // Set a reasonable upper bound for memory (e.g., 128 GB)
-        const MAX_MEMORY_MB: u32 = 128 * 1024; // 131072 MB
-        if memory_mb == 0 {
-            return Err(InstanceError::invalid_config(
-                "Memory must be greater than 0 MB".to_string(),
-            )
-            .into());
-        }
-        if memory_mb > MAX_MEMORY_MB {
-            return Err(InstanceError::invalid_config(format!(
-                "Memory value too large ({memory_mb} MB). Maximum allowed is {MAX_MEMORY_MB} MB (128 GB)"
-            ))
-            .into());
+        {
+            let _dir_lock = self.lock_instances_dir().await?;
+            history::append_history(&self.get_instance_dir(name), record).await?;
         }
-        if let Some(config) = self.instances.get_mut(name) {
-            config.settings.memory_mb = Some(memory_mb);
-            // Clone the config to avoid borrow checker issues
-            let config_clone = config.clone();
-            self.save_instance_config(&config_clone).await?;
-            info!("Set memory for instance '{name}' to {memory_mb}MB");
-        } else {
+        self.update_instance(name, |config| {
+            config.playtime_secs = config.playtime_secs.saturating_add(duration);
+            config.launch_count = config.launch_count.saturating_add(1);
+        })
+        .await
+    }
+
+    /// Load the launch history of an instance, oldest session first
+    pub async fn get_launch_history(&self, name: &str) -> Result<Vec<LaunchRecord>> {
+        if !self.instances.contains_key(name) {
             return Err(
                 InstanceError::not_found(format!("Instance '{name}' does not exist")).into(),
             );
         }
-        Ok(())
+        history::load_history(&self.get_instance_dir(name)).await
+    }
+
+    /// Pin an instance to a Java executable or major version, or clear both with `None`s
+    pub async fn set_instance_java(
+        &mut self,
+        name: &str,
+        java_path: Option<PathBuf>,
+        java_version: Option<u32>,
+    ) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.java_path = java_path;
+            config.settings.java_version = java_version;
+        })
+        .await
+    }
+
+    /// Select the GC flag preset of an instance
+    pub async fn set_instance_jvm_preset(&mut self, name: &str, preset: JvmPreset) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.jvm_preset = preset;
+        })
+        .await
+    }
+
+    /// Set the server or world an instance joins on launch, or clear both with `None`s
+    pub async fn set_instance_quick_play(
+        &mut self,
+        name: &str,
+        server: Option<ServerConfig>,
+        world: Option<String>,
+    ) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.server = server;
+            config.settings.world = world;
+        })
+        .await
+    }
+
+    /// Set the game window size and mode of an instance, `None` sizes use the game's default
+    pub async fn set_instance_window(
+        &mut self,
+        name: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        fullscreen: bool,
+    ) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.width = width;
+            config.settings.height = height;
+            config.settings.fullscreen = fullscreen;
+        })
+        .await
+    }
+
+    /// Set the hooks and wrapper of an instance, `None` removes them
+    pub async fn set_instance_hooks(
+        &mut self,
+        name: &str,
+        pre_launch: Option<String>,
+        post_exit: Option<String>,
+        wrapper: Option<String>,
+    ) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.pre_launch = pre_launch;
+            config.settings.post_exit = post_exit;
+            config.settings.wrapper = wrapper;
+        })
+        .await
+    }
+
+    /// Set the environment variables of an instance and whether it is sanitized
+    pub async fn set_instance_env(
+        &mut self,
+        name: &str,
+        env: BTreeMap<String, Option<String>>,
+        sanitize_env: bool,
+    ) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.env = env;
+            config.settings.sanitize_env = sanitize_env;
+        })
+        .await
+    }
+
+    /// Add and remove tags on an instance
+    pub async fn update_instance_tags(
+        &mut self,
+        name: &str,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<()> {
+        let add = normalize_labels(add)?;
+        let remove = normalize_labels(remove)?;
+
+        self.update_instance(name, |config| {
+            config
+                .tags
+                .retain(|tag| !remove.iter().any(|r| r.eq_ignore_ascii_case(tag)));
+            for tag in add {
+                if !config.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                    config.tags.push(tag);
+                }
+            }
+            config.tags.sort();
+        })
+        .await
+    }
+
+    /// Set or clear the group of an instance
+    pub async fn set_instance_group(&mut self, name: &str, group: Option<String>) -> Result<()> {
+        let group = match group {
+            Some(group) => normalize_labels(&[group])?.pop(),
+            None => None,
+        };
+
+        self.update_instance(name, |config| {
+            config.group = group;
+        })
+        .await
+    }
+
+    /// Get the directory path for an instance
+    pub fn get_instance_dir(&self, name: &str) -> PathBuf {
+        self.instances_dir.join(name)
+    }
+
+    /// Set the initial and maximum heap of an instance, `None` uses the default
+    pub async fn set_instance_memory(
+        &mut self,
+        name: &str,
+        min_memory: Option<MemorySize>,
+        max_memory: Option<MemorySize>,
+    ) -> Result<()> {
+        self.update_instance(name, |config| {
+            config.settings.min_memory = min_memory;
+            config.settings.max_memory = max_memory;
+        })
+        .await
     }
 
     /// Create instance game directory and ensure it's properly set up
@@ -367,6 +809,109 @@ impl InstanceManager {
     }
 }
 
+/// Trim tag or group names and reject ones that are empty or contain whitespace
+fn normalize_labels(labels: &[String]) -> Result<Vec<String>> {
+    labels
+        .iter()
+        .map(|label| {
+            let label = label.trim();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                Err(InstanceError::invalid_config(format!(
+                    "Invalid tag or group name '{label}': must be non-empty and contain no spaces"
+                ))
+                .into())
+            } else {
+                Ok(label.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Upgrade a raw instance config to the current schema
+///
+/// Returns the parsed config along with the schema version it was stored with.
+fn migrate_instance_config(mut value: serde_json::Value) -> Result<(InstanceConfig, u32)> {
+    let object = value.as_object_mut().ok_or_else(|| {
+        InstanceError::invalid_config("Instance config must be a JSON object".to_string())
+    })?;
+
+    let original_version = match object.get("schema_version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| InstanceError::invalid_config(format!("Invalid schema_version: {v}")))?,
+    };
+
+    if original_version > CURRENT_SCHEMA_VERSION {
+        return Err(InstanceError::invalid_config(format!(
+            "Instance config uses schema v{original_version}, but this launcher only supports up to v{CURRENT_SCHEMA_VERSION}. Please update rustified."
+        ))
+        .into());
+    }
+
+    for (from_version, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(original_version as usize)
+    {
+        debug!(
+            "Migrating instance config from schema v{from_version} to v{}",
+            from_version + 1
+        );
+        migration(object);
+    }
+    object.insert(
+        "schema_version".to_string(),
+        serde_json::Value::from(CURRENT_SCHEMA_VERSION),
+    );
+
+    let config: InstanceConfig =
+        serde_json::from_value(value).context("Failed to parse instance config")?;
+
+    Ok((config, original_version))
+}
+
+/// v1 -> v2: `memory_mb` becomes the maximum heap, with half of it as the initial heap as before
+fn migrate_v1_to_v2(object: &mut serde_json::Map<String, serde_json::Value>) {
+    let Some(settings) = object
+        .get_mut("settings")
+        .and_then(serde_json::Value::as_object_mut)
+    else {
+        return;
+    };
+    let Some(memory_mb) = settings.remove("memory_mb") else {
+        return;
+    };
+    let Some(memory_mb) = memory_mb
+        .as_u64()
+        .and_then(|mb| u32::try_from(mb).ok())
+        .filter(|mb| (1..=MAX_MEMORY_MB).contains(mb))
+    else {
+        return;
+    };
+
+    settings.insert(
+        "max_memory".to_string(),
+        MemorySize::Megabytes(memory_mb).to_string().into(),
+    );
+    if memory_mb >= 2 {
+        settings.insert(
+            "min_memory".to_string(),
+            MemorySize::Megabytes(memory_mb / 2).to_string().into(),
+        );
+    }
+}
+
+/// v0 (unversioned) -> v1: drop explicit nulls for sections that are now defaulted
+fn migrate_v0_to_v1(object: &mut serde_json::Map<String, serde_json::Value>) {
+    for key in ["settings", "mods"] {
+        if object.get(key).is_some_and(serde_json::Value::is_null) {
+            object.remove(key);
+        }
+    }
+}
+
 impl Default for ModsConfig {
     fn default() -> Self {
         Self {
@@ -376,3 +921,123 @@ impl Default for ModsConfig {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+
+    #[test]
+    fn test_migrate_unversioned_config() {
+        let value = serde_json::json!({
+            "name": "legacy",
+            "version": "1.20.4",
+            "created": "2024-01-01T00:00:00Z",
+            "settings": null
+        });
+
+        let (config, original_version) = migrate_instance_config(value).unwrap();
+        assert_eq!(original_version, 0);
+        assert_eq!(config.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(config.name, "legacy");
+        assert!(config.description.is_none());
+        assert!(config.settings.max_memory.is_none());
+        assert!(matches!(config.mods.loader, ModLoader::Vanilla));
+    }
+
+    #[test]
+    fn test_migrate_current_config_is_noop() {
+        let value = serde_json::json!({
+            "schema_version": CURRENT_SCHEMA_VERSION,
+            "name": "modern",
+            "version": "1.21",
+            "created": "2024-01-01T00:00:00Z",
+            "settings": { "max_memory": "50%" },
+            "unknown_future_field": true
+        });
+
+        let (config, original_version) = migrate_instance_config(value).unwrap();
+        assert_eq!(original_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(config.settings.max_memory, Some(MemorySize::Percent(50)));
+    }
+
+    #[test]
+    fn test_migrate_memory_mb_to_heap_sizes() {
+        let value = serde_json::json!({
+            "schema_version": 1,
+            "name": "old-memory",
+            "version": "1.20.4",
+            "created": "2024-01-01T00:00:00Z",
+            "settings": { "memory_mb": 4096 }
+        });
+
+        let (config, _) = migrate_instance_config(value).unwrap();
+        assert_eq!(
+            config.settings.max_memory,
+            Some(MemorySize::Megabytes(4096))
+        );
+        assert_eq!(
+            config.settings.min_memory,
+            Some(MemorySize::Megabytes(2048))
+        );
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_schema() {
+        let value = serde_json::json!({
+            "schema_version": CURRENT_SCHEMA_VERSION + 1,
+            "name": "future",
+            "version": "1.21",
+            "created": "2024-01-01T00:00:00Z"
+        });
+
+        assert!(migrate_instance_config(value).is_err());
+    }
+
+    #[test]
+    fn test_parse_server_config() {
+        let server = ServerConfig::parse("play.example.com:25566").unwrap();
+        assert_eq!(server.address, "play.example.com");
+        assert_eq!(server.port, Some(25566));
+        assert_eq!(server.to_string(), "play.example.com:25566");
+
+        let server = ServerConfig::parse("mc.example.com").unwrap();
+        assert_eq!(server.port, None);
+        assert_eq!(server.to_string(), "mc.example.com:25565");
+
+        let server = ServerConfig::parse("[::1]:25570").unwrap();
+        assert_eq!(server.address, "::1");
+        assert_eq!(server.to_string(), "[::1]:25570");
+
+        assert!(ServerConfig::parse("host:notaport").is_err());
+        assert!(ServerConfig::parse(":25565").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delete_refuses_running_instance() {
+        let minecraft_dir = test_support::minecraft_dir("instance-delete");
+        let base_path = minecraft_dir.base_path.clone();
+        let instance_dir = base_path.join("instances").join("demo");
+        std::fs::create_dir_all(&instance_dir).unwrap();
+        let config = serde_json::json!({
+            "name": "demo",
+            "version": "1.20.4",
+            "created": "2024-01-01T00:00:00Z"
+        });
+        std::fs::write(instance_dir.join("instance.json"), config.to_string()).unwrap();
+        let mut manager = InstanceManager::new(minecraft_dir).await.unwrap();
+
+        let running = manager.lock_instance("demo").await.unwrap();
+        assert!(manager.delete_instance("demo").await.is_err());
+        assert!(instance_dir.join("instance.json").exists());
+
+        drop(running);
+        manager.delete_instance("demo").await.unwrap();
+        assert!(!instance_dir.exists());
+        // A launch after the deletion finds nothing to lock
+        assert!(manager.lock_instance("demo").await.is_err());
+
+        std::fs::remove_dir_all(&base_path).unwrap();
+    }
+}
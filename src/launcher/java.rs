@@ -1,16 +1,59 @@
 use crate::error::{JavaError, Result, ResultExt};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tracing::{debug, warn};
 
-use crate::launcher;
+use crate::launcher::java_cache::{self, ProbeCache};
+use crate::launcher::minecraft_dir::MinecraftDir;
+use crate::launcher::runtime;
+use crate::launcher::version::VersionInfo;
 
-#[derive(Debug, Clone)]
+/// Main class of `LaunchWrapper`, which casts the system class loader and breaks on Java 9+
+const LAUNCHWRAPPER_MAIN_CLASS: &str = "net.minecraft.launchwrapper.Launch";
+
+/// Name of the java executable on this platform
+const JAVA_EXECUTABLE: &str = if cfg!(windows) { "java.exe" } else { "java" };
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct JavaInstallation {
     pub path: PathBuf,
     pub major_version: u32,
+    /// Full version string, e.g. `17.0.8` or `1.8.0_382`
+    pub version: String,
+    /// Vendor reported by the `java.vendor` property
+    pub vendor: Option<String>,
+    /// CPU architecture using Rust's names (e.g. `x86_64`, `aarch64`)
+    pub arch: Option<String>,
+    pub is_64bit: bool,
+}
+
+impl JavaInstallation {
+    /// Whether this JVM runs natively on the current CPU
+    pub fn is_native(&self) -> bool {
+        self.arch.as_deref() == Some(env::consts::ARCH)
+    }
+
+    /// Sort key for choosing between installations, lower is better
+    fn preference(&self) -> (bool, bool) {
+        (!self.is_native(), !self.is_64bit)
+    }
+
+    /// Version, vendor and architecture, e.g. "17.0.8, Eclipse Adoptium, `x86_64` 64-bit"
+    pub fn describe(&self) -> String {
+        let mut parts = vec![self.version.clone()];
+        if let Some(vendor) = &self.vendor {
+            parts.push(vendor.clone());
+        }
+        let bits = if self.is_64bit { "64-bit" } else { "32-bit" };
+        parts.push(match &self.arch {
+            Some(arch) => format!("{arch} {bits}"),
+            None => bits.to_string(),
+        });
+        parts.join(", ")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,21 +63,105 @@ pub struct JavaVersion {
     pub patch: u32,
 }
 
+/// Where a Java requirement came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JavaRequirementSource {
+    /// The `javaVersion` field of the version JSON
+    VersionJson,
+    /// The built-in table of Minecraft versions
+    VersionTable,
+}
+
+impl std::fmt::Display for JavaRequirementSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VersionJson => write!(f, "from the version JSON"),
+            Self::VersionTable => write!(f, "from the built-in version table"),
+        }
+    }
+}
+
+/// The Java versions a Minecraft version can run on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaRequirement {
+    /// Minimum (and preferred) Java major version
+    pub major: u32,
+    /// Newest Java major version known to work, if there is a limit
+    pub max_major: Option<u32>,
+    pub source: JavaRequirementSource,
+}
+
+impl JavaRequirement {
+    /// Whether a Java major version falls inside this requirement
+    pub fn allows(&self, major: u32) -> bool {
+        major >= self.major && self.max_major.is_none_or(|max| major <= max)
+    }
+}
+
+impl std::fmt::Display for JavaRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max_major {
+            Some(max) if max == self.major => write!(f, "Java {}", self.major),
+            Some(max) => write!(f, "Java {} to {max}", self.major),
+            None => write!(f, "Java {} or newer", self.major),
+        }
+    }
+}
+
 pub struct JavaManager {
-    pub installations: HashMap<u32, JavaInstallation>,
+    pub installations: Vec<JavaInstallation>,
+    probe_cache: ProbeCache,
 }
 
 impl JavaManager {
     pub fn new() -> Self {
         Self {
-            installations: HashMap::new(),
+            installations: Vec::new(),
+            probe_cache: ProbeCache::default(),
         }
     }
 
     /// Initialize the Java manager by scanning for Java installations
-    pub fn initialize(&mut self) {
+    ///
+    /// Probe results are reused from the discovery cache while executables are unchanged.
+    pub async fn initialize(&mut self, minecraft_dir: &MinecraftDir) {
+        self.probe_cache = ProbeCache::load(&minecraft_dir.java_cache_path());
+        self.scan(minecraft_dir).await;
+    }
+
+    /// Forget cached probe results and scan every installation again
+    pub async fn refresh(&mut self, minecraft_dir: &MinecraftDir) {
+        self.installations.clear();
+        self.probe_cache.clear();
+        self.scan(minecraft_dir).await;
+    }
+
+    /// Scan every known location and persist the discovery cache
+    async fn scan(&mut self, minecraft_dir: &MinecraftDir) {
         debug!("Scanning for Java installations...");
+        for java_path in java_cache::load_user_java_paths(&minecraft_dir.user_java_paths_path()) {
+            if let Some(installation) = self.probe_cached(&java_path) {
+                self.add_installation(installation, "java add");
+            } else {
+                warn!(
+                    "Registered Java installation is not usable: {}",
+                    java_path.display()
+                );
+            }
+        }
         self.scan_java_installations();
+        self.scan_managed_runtimes(&minecraft_dir.runtime_dir());
+
+        if let Err(e) = self
+            .probe_cache
+            .save(&minecraft_dir.java_cache_path())
+            .await
+        {
+            warn!(
+                "Failed to save Java discovery cache: {}",
+                e.to_string().lines().next().unwrap_or_default()
+            );
+        }
 
         if self.installations.is_empty() {
             warn!(
@@ -42,8 +169,13 @@ impl JavaManager {
             );
         } else {
             debug!("Found {} Java installation(s)", self.installations.len());
-            for (major, installation) in &self.installations {
-                debug!("Java {}: {}", major, installation.path.display());
+            for installation in &self.installations {
+                debug!(
+                    "Java {} ({}): {}",
+                    installation.major_version,
+                    installation.describe(),
+                    installation.path.display()
+                );
             }
         }
     }
@@ -65,6 +197,8 @@ impl JavaManager {
                 (1, 16) => 11,
                 // Minecraft 1.15 and below require Java 8
                 (1, minor) if minor <= 15 => 8,
+                // Year-based version ids (e.g. 25.1) are newer than 1.21
+                (year, _) if year >= 25 => 21,
                 // Default to Java 17 for unknown versions
                 _ => 17,
             }
@@ -74,83 +208,302 @@ impl JavaManager {
         }
     }
 
-    /// Get the best Java installation for a Minecraft version
-    pub fn get_java_for_minecraft(&self, minecraft_version: &str) -> Result<&JavaInstallation> {
-        let required_version = Self::get_required_java_version(minecraft_version);
+    /// Work out which Java a Minecraft version needs
+    ///
+    /// The version JSON's `javaVersion` wins; the version table is only used for
+    /// JSONs that predate it.
+    pub fn required_java(version_info: &VersionInfo) -> JavaRequirement {
+        let (major, source) = match &version_info.java_version {
+            Some(java_version) => (
+                java_version.major_version,
+                JavaRequirementSource::VersionJson,
+            ),
+            None => (
+                Self::get_required_java_version(&version_info.id),
+                JavaRequirementSource::VersionTable,
+            ),
+        };
+
+        JavaRequirement {
+            major,
+            max_major: java_ceiling(version_info),
+            source,
+        }
+    }
+
+    /// Get the best Java installation for a Java requirement
+    pub fn get_java_for_requirement(
+        &self,
+        requirement: &JavaRequirement,
+    ) -> Result<&JavaInstallation> {
+        let required_version = requirement.major;
 
         // First, try to find the exact required version
-        if let Some(installation) = self.installations.get(&required_version) {
-            debug!("Using Java {required_version} for Minecraft {minecraft_version}");
+        if let Some(installation) = self.best_for_major(required_version) {
+            debug!("Using Java {required_version} ({})", requirement.source);
             return Ok(installation);
         }
 
         // If exact version not found, try to find a compatible higher version
-        let mut compatible_versions: Vec<_> = self
-            .installations
+        let majors = self.majors();
+        if let Some(installation) = majors
             .iter()
-            .filter(|(major, _)| **major >= required_version)
-            .collect();
-
-        compatible_versions.sort_by_key(|(major, _)| *major);
-
-        if let Some((major, installation)) = compatible_versions.first() {
+            .find(|major| requirement.allows(**major))
+            .and_then(|major| self.best_for_major(*major))
+        {
             warn!(
-                "Required Java {required_version} not found for Minecraft {minecraft_version}, using Java {major} instead"
+                "Required Java {required_version} not found, using Java {} instead",
+                installation.major_version
             );
             return Ok(installation);
         }
 
-        // If no compatible version found, use the highest available version
-        let mut all_versions: Vec<_> = self.installations.iter().collect();
-        all_versions.sort_by_key(|(major, _)| *major);
+        // Versions with a ceiling would crash on anything newer, so don't try
+        if let Some(max_major) = requirement.max_major {
+            return Err(JavaError::unsupported_version(format!(
+                "This version needs Java {required_version} to {max_major}, but only {} installed",
+                self.describe_installed()
+            ))
+            .into());
+        }
 
-        if let Some((major, installation)) = all_versions.last() {
+        // If no compatible version found, use the highest available version
+        if let Some(installation) = majors.last().and_then(|major| self.best_for_major(*major)) {
             warn!(
-                "No compatible Java version found for Minecraft {minecraft_version} (requires Java {required_version}), using Java {major} - this may not work!"
+                "No compatible Java version found (requires Java {required_version}), using Java {} - this may not work!",
+                installation.major_version
             );
             return Ok(installation);
         }
 
         Err(JavaError::not_found(format!(
-            "No Java installations found! Please install Java {required_version} or higher for Minecraft {minecraft_version}"
+            "No Java installations found! Please install Java {required_version} or higher"
         ))
         .into())
     }
 
+    /// Pick the Java installation for a launch, honouring per-instance overrides
+    ///
+    /// An explicit `java_path` wins over a pinned `java_version`, which wins over
+    /// automatic selection for the requirement.
+    pub fn resolve_java(
+        &self,
+        requirement: &JavaRequirement,
+        java_path: Option<&Path>,
+        java_version: Option<u32>,
+    ) -> Result<JavaInstallation> {
+        let installation = if let Some(path) = java_path {
+            debug!("Using instance Java override: {}", path.display());
+            Self::probe_java_installation(path).with_context(|| {
+                format!(
+                    "Java override for this instance is not usable: {}",
+                    path.display()
+                )
+            })?
+        } else if let Some(major) = java_version {
+            debug!("Using pinned Java {major} for this instance");
+            self.best_for_major(major).cloned().ok_or_else(|| {
+                JavaError::not_found(format!(
+                    "Java {major} is pinned for this instance but no such installation was found"
+                ))
+            })?
+        } else {
+            return self.get_java_for_requirement(requirement).cloned();
+        };
+
+        // Overrides are the user's call, but say when they are likely to fail
+        if !requirement.allows(installation.major_version) {
+            warn!(
+                "Instance uses Java {}, but this version needs {requirement}",
+                installation.major_version
+            );
+        }
+
+        Ok(installation)
+    }
+
+    /// Whether any installation of a Java major version is known
+    pub fn has_major(&self, major: u32) -> bool {
+        self.installations
+            .iter()
+            .any(|installation| installation.major_version == major)
+    }
+
+    /// The preferred installation of a Java major version
+    ///
+    /// Native-architecture 64-bit runtimes win over emulated or 32-bit ones.
+    pub fn best_for_major(&self, major: u32) -> Option<&JavaInstallation> {
+        self.installations
+            .iter()
+            .filter(|installation| installation.major_version == major)
+            .min_by_key(|installation| installation.preference())
+    }
+
+    /// Installed Java major versions, lowest first
+    pub fn majors(&self) -> Vec<u32> {
+        let mut majors: Vec<_> = self
+            .installations
+            .iter()
+            .map(|installation| installation.major_version)
+            .collect();
+        majors.sort_unstable();
+        majors.dedup();
+        majors
+    }
+
+    /// Short list of installed major versions for error messages
+    fn describe_installed(&self) -> String {
+        let majors = self.majors();
+        if majors.is_empty() {
+            return "no Java is".to_string();
+        }
+        let list = majors
+            .iter()
+            .map(|major| format!("Java {major}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{list} {}", if majors.len() == 1 { "is" } else { "are" })
+    }
+
+    /// Register a Java installation, replacing a previous entry for the same executable
+    pub fn register(&mut self, installation: JavaInstallation) {
+        debug!(
+            "Registered Java {}: {}",
+            installation.major_version,
+            installation.path.display()
+        );
+        let key = canonical_path(&installation.path);
+        self.installations
+            .retain(|existing| canonical_path(&existing.path) != key);
+        self.installations.push(installation);
+    }
+
+    /// Add a discovered installation unless the same executable is already known
+    fn add_installation(&mut self, installation: JavaInstallation, source: &str) {
+        let key = canonical_path(&installation.path);
+        if self
+            .installations
+            .iter()
+            .any(|existing| canonical_path(&existing.path) == key)
+        {
+            return;
+        }
+
+        debug!(
+            "Found Java {} via {source}: {}",
+            installation.major_version,
+            installation.path.display()
+        );
+        self.installations.push(installation);
+    }
+
+    /// Probe a java executable and add it if it runs
+    fn add_java_executable(&mut self, java_path: &Path, source: &str) {
+        if let Some(installation) = self.probe_cached(java_path) {
+            self.add_installation(installation, source);
+        }
+    }
+
+    /// Probe a java executable, reusing the cached result while it is unchanged
+    fn probe_cached(&mut self, java_path: &Path) -> Option<JavaInstallation> {
+        if let Some(installation) = self.probe_cache.get(java_path) {
+            return Some(installation);
+        }
+
+        let installation = Self::probe_java_installation(java_path).ok()?;
+        self.probe_cache.insert(java_path, &installation);
+        Some(installation)
+    }
+
+    /// Register a Java installation in a non-standard location so it is found on every run
+    ///
+    /// `path` may be a java executable or a Java home directory.
+    pub async fn add_user_java(
+        &mut self,
+        minecraft_dir: &MinecraftDir,
+        path: &Path,
+    ) -> Result<JavaInstallation> {
+        let java_path = java_executable_for(path);
+        let installation = Self::probe_java_installation(&java_path)?;
+
+        let key = canonical_path(&java_path);
+        java_cache::update_user_java_paths(&minecraft_dir.user_java_paths_path(), |paths| {
+            if !paths.iter().any(|existing| canonical_path(existing) == key) {
+                paths.push(java_path.clone());
+            }
+        })
+        .await?;
+
+        self.probe_cache.insert(&java_path, &installation);
+        self.register(installation.clone());
+        Ok(installation)
+    }
+
+    /// Unregister a Java installation added with [`JavaManager::add_user_java`]
+    pub async fn remove_user_java(
+        &mut self,
+        minecraft_dir: &MinecraftDir,
+        path: &Path,
+    ) -> Result<()> {
+        let key = canonical_path(&java_executable_for(path));
+        let removed =
+            java_cache::update_user_java_paths(&minecraft_dir.user_java_paths_path(), |paths| {
+                let before = paths.len();
+                paths.retain(|existing| canonical_path(existing) != key);
+                before != paths.len()
+            })
+            .await?;
+
+        if !removed {
+            return Err(JavaError::not_found(format!(
+                "{} is not a registered Java installation",
+                path.display()
+            ))
+            .into());
+        }
+
+        self.installations
+            .retain(|installation| canonical_path(&installation.path) != key);
+        Ok(())
+    }
+
+    /// Scan runtimes downloaded from Mojang's java-runtime manifest
+    fn scan_managed_runtimes(&mut self, runtime_dir: &Path) {
+        let Some(platform) = runtime::runtime_platform() else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(runtime_dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let component = entry.file_name();
+            let java_path =
+                runtime::runtime_java_executable(&entry.path().join(platform).join(&component));
+            self.add_java_executable(&java_path, "managed runtime");
+        }
+    }
+
     /// Scan for Java installations in common locations
     fn scan_java_installations(&mut self) {
         // Check JAVA_HOME first
         if let Ok(java_home) = env::var("JAVA_HOME") {
-            let java_path = PathBuf::from(java_home).join("bin").join(if cfg!(windows) {
-                "java.exe"
-            } else {
-                "java"
-            });
-
-            if let Ok(installation) =
-                launcher::java::JavaManager::probe_java_installation(&java_path)
-            {
-                debug!("Found Java via JAVA_HOME: {}", installation.path.display());
-                self.installations
-                    .insert(installation.major_version, installation);
-            }
+            let java_path = PathBuf::from(java_home).join("bin").join(JAVA_EXECUTABLE);
+            self.add_java_executable(&java_path, "JAVA_HOME");
         }
 
         // Check system PATH
-        let java_executable = if cfg!(windows) { "java.exe" } else { "java" };
-        if let Ok(installation) =
-            launcher::java::JavaManager::probe_java_installation_by_name(java_executable)
-        {
-            if let std::collections::hash_map::Entry::Vacant(e) =
-                self.installations.entry(installation.major_version)
-            {
-                debug!("Found Java via PATH: {}", installation.path.display());
-                e.insert(installation);
-            }
+        if let Some(java_path) = find_on_path(JAVA_EXECUTABLE) {
+            self.add_java_executable(&java_path, "PATH");
         }
 
         // Check common installation directories
         self.scan_common_java_directories();
+
+        // Check JDKs installed by version managers and IDEs
+        for (base_path, source) in version_manager_dirs() {
+            self.scan_java_homes(&base_path, source);
+        }
     }
 
     /// Scan common Java installation directories
@@ -173,31 +526,7 @@ impl JavaManager {
         };
 
         for base_path in common_paths {
-            if let Ok(entries) = std::fs::read_dir(base_path) {
-                for entry in entries.flatten() {
-                    if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                        let java_path = entry.path().join("bin").join(if cfg!(windows) {
-                            "java.exe"
-                        } else {
-                            "java"
-                        });
-
-                        if let Ok(installation) =
-                            launcher::java::JavaManager::probe_java_installation(&java_path)
-                        {
-                            if let std::collections::hash_map::Entry::Vacant(e) =
-                                self.installations.entry(installation.major_version)
-                            {
-                                debug!(
-                                    "Found Java in common directory: {}",
-                                    installation.path.display()
-                                );
-                                e.insert(installation);
-                            }
-                        }
-                    }
-                }
-            }
+            self.scan_java_homes(Path::new(base_path), "common directory");
         }
 
         // Special handling for macOS java_home
@@ -206,67 +535,54 @@ impl JavaManager {
         }
     }
 
-    /// Scan Java installations using macOS `java_home` utility
-    fn scan_macos_java_home(&mut self) {
-        let versions = ["8", "11", "16", "17", "21"];
-
-        for version in &versions {
-            if let Ok(output) = Command::new("/usr/libexec/java_home")
-                .arg("-v")
-                .arg(version)
-                .output()
-            {
-                if output.status.success() {
-                    let java_home = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    let java_path = PathBuf::from(java_home).join("bin").join("java");
-
-                    if let Ok(installation) =
-                        launcher::java::JavaManager::probe_java_installation(&java_path)
-                    {
-                        if let std::collections::hash_map::Entry::Vacant(e) =
-                            self.installations.entry(installation.major_version)
-                        {
-                            debug!("Found Java via java_home: {}", installation.path.display());
-                            e.insert(installation);
-                        }
-                    }
-                }
+    /// Probe every Java home directly inside `base_path`
+    fn scan_java_homes(&mut self, base_path: &Path, source: &str) {
+        let Ok(entries) = std::fs::read_dir(base_path) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let java_home = entry.path();
+            if !java_home.is_dir() {
+                continue;
+            }
+
+            if let Some(java_path) = java_in_home(&java_home) {
+                self.add_java_executable(&java_path, source);
             }
         }
     }
 
-    /// Probe a Java installation by executable name
-    fn probe_java_installation_by_name(executable: &str) -> Result<JavaInstallation> {
-        if let Ok(output) = Command::new(executable).arg("-version").output() {
-            if output.status.success() {
-                let version_output = String::from_utf8_lossy(&output.stderr);
-                if let Some(version) = parse_java_version(&version_output) {
-                    // Try to find the actual path
-                    let path =
-                        if let Ok(which_output) = Command::new("which").arg(executable).output() {
-                            let path_str = String::from_utf8_lossy(&which_output.stdout)
-                                .trim()
-                                .to_string();
-                            PathBuf::from(path_str)
-                        } else {
-                            PathBuf::from(executable)
-                        };
-
-                    return Ok(JavaInstallation {
-                        path,
-                        major_version: version.major,
-                    });
-                }
-            }
+    /// Scan Java installations using macOS `java_home` utility
+    ///
+    /// The listing is cached until a JVM directory changes, so the tool is not
+    /// spawned on every discovery.
+    fn scan_macos_java_home(&mut self) {
+        let mut jvm_dirs = vec![PathBuf::from("/Library/Java/JavaVirtualMachines")];
+        if let Some(home) = env::var_os("HOME") {
+            jvm_dirs.push(PathBuf::from(home).join("Library/Java/JavaVirtualMachines"));
+        }
+
+        let homes = if let Some(homes) = self.probe_cache.java_homes(&jvm_dirs) {
+            homes
+        } else {
+            let Ok(output) = Command::new("/usr/libexec/java_home").arg("-V").output() else {
+                return;
+            };
+            // The listing goes to stderr, the default home to stdout
+            let homes = parse_java_home_listing(&String::from_utf8_lossy(&output.stderr));
+            self.probe_cache.set_java_homes(&jvm_dirs, homes.clone());
+            homes
+        };
+
+        for java_home in homes {
+            let java_path = java_home.join("bin").join("java");
+            self.add_java_executable(&java_path, "java_home");
         }
-        Err(
-            JavaError::execution_failed(format!("Failed to probe Java installation: {executable}"))
-                .into(),
-        )
     }
 
     /// Probe a specific Java installation path
-    fn probe_java_installation(java_path: &Path) -> Result<JavaInstallation> {
+    pub fn probe_java_installation(java_path: &Path) -> Result<JavaInstallation> {
         if !java_path.exists() {
             return Err(JavaError::not_found(format!(
                 "Java executable not found: {}",
@@ -275,88 +591,231 @@ impl JavaManager {
             .into());
         }
 
+        let output = run_java_probe(java_path)?;
+        parse_java_installation(java_path, &output).ok_or_else(|| {
+            JavaError::version_parsing(format!("Failed to parse Java version: {output}")).into()
+        })
+    }
+}
+
+/// Java homes listed by `java_home -V`
+///
+/// Each installation is an indented line such as
+/// `17.0.8 (x86_64) "Eclipse Adoptium" - "OpenJDK 17.0.8" /Library/Java/.../Home`.
+fn parse_java_home_listing(listing: &str) -> Vec<PathBuf> {
+    let mut homes: Vec<PathBuf> = Vec::new();
+    for line in listing
+        .lines()
+        .filter(|line| line.starts_with(char::is_whitespace))
+    {
+        if let Some(start) = line.find(" /") {
+            let home = PathBuf::from(line[start + 1..].trim_end());
+            if !homes.contains(&home) {
+                homes.push(home);
+            }
+        }
+    }
+    homes
+}
+
+/// Newest Java major version a Minecraft version is known to run on
+fn java_ceiling(version_info: &VersionInfo) -> Option<u32> {
+    let uses_launchwrapper = version_info.main_class == LAUNCHWRAPPER_MAIN_CLASS
+        || version_info
+            .libraries
+            .iter()
+            .any(|library| library.name.starts_with("net.minecraft:launchwrapper:"));
+
+    uses_launchwrapper.then_some(8)
+}
+
+/// Directories where version managers and IDEs keep downloaded JDKs
+fn version_manager_dirs() -> Vec<(PathBuf, &'static str)> {
+    let Some(home) = env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+    else {
+        return Vec::new();
+    };
+    let dir_from_env = |var: &str, default: &str| {
+        env::var_os(var).map_or_else(|| home.join(default), PathBuf::from)
+    };
+
+    vec![
+        (
+            dir_from_env("SDKMAN_DIR", ".sdkman")
+                .join("candidates")
+                .join("java"),
+            "SDKMAN",
+        ),
+        (
+            dir_from_env("ASDF_DATA_DIR", ".asdf")
+                .join("installs")
+                .join("java"),
+            "asdf",
+        ),
+        (dir_from_env("JABBA_HOME", ".jabba").join("jdk"), "jabba"),
+        (
+            dir_from_env("GRADLE_USER_HOME", ".gradle").join("jdks"),
+            "Gradle toolchains",
+        ),
+        (home.join(".jdks"), "~/.jdks"),
+    ]
+}
+
+/// The java executable inside a Java home, if there is one
+fn java_in_home(java_home: &Path) -> Option<PathBuf> {
+    // macOS bundles keep the actual home under Contents/Home
+    [
+        java_home.join("bin").join(JAVA_EXECUTABLE),
+        java_home
+            .join("Contents")
+            .join("Home")
+            .join("bin")
+            .join(JAVA_EXECUTABLE),
+    ]
+    .into_iter()
+    .find(|path| path.exists())
+}
+
+/// Accept either a java executable or a Java home directory
+fn java_executable_for(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        java_in_home(path).unwrap_or_else(|| path.join("bin").join(JAVA_EXECUTABLE))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// First match for an executable name in the PATH directories
+fn find_on_path(executable: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(executable))
+        .find(|path| path.is_file())
+}
+
+/// Path used to recognise the same executable reached through different symlinks
+fn canonical_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Run `java -XshowSettings:properties -version` and return what it printed
+///
+/// Falls back to plain `-version` for JVMs that reject the settings flag.
+fn run_java_probe(java_path: &Path) -> Result<String> {
+    for args in [
+        &["-XshowSettings:properties", "-version"][..],
+        &["-version"],
+    ] {
         let output = Command::new(java_path)
-            .arg("-version")
+            .args(args)
             .output()
             .with_context(|| format!("Failed to execute Java: {}", java_path.display()))?;
 
-        if !output.status.success() {
-            return Err(JavaError::execution_failed(format!(
-                "Java version check failed: {}",
-                java_path.display()
-            ))
-            .into());
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stderr).into_owned());
         }
+    }
 
-        let version_output = String::from_utf8_lossy(&output.stderr);
-        let version = parse_java_version(&version_output).ok_or_else(|| {
-            JavaError::version_parsing(format!("Failed to parse Java version: {version_output}"))
-        })?;
+    Err(JavaError::execution_failed(format!(
+        "Java version check failed: {}",
+        java_path.display()
+    ))
+    .into())
+}
 
-        Ok(JavaInstallation {
-            path: java_path.to_path_buf(),
-            major_version: version.major,
-        })
+/// Build an installation from the output of [`run_java_probe`]
+fn parse_java_installation(java_path: &Path, output: &str) -> Option<JavaInstallation> {
+    let properties = parse_java_properties(output);
+
+    let (version, major) = if let Some(version) = properties.get("java.version") {
+        (version.clone(), parse_version_string(version)?.major)
+    } else {
+        (
+            quoted_version(output)?.to_string(),
+            parse_java_version(output)?.major,
+        )
+    };
+    let arch = properties.get("os.arch").map(|arch| normalize_arch(arch));
+    let is_64bit = match properties.get("sun.arch.data.model").map(String::as_str) {
+        Some(model) => model == "64",
+        None => arch
+            .as_deref()
+            .is_some_and(|arch| arch.ends_with("64") || arch == "s390x"),
+    };
+
+    Some(JavaInstallation {
+        path: java_path.to_path_buf(),
+        major_version: major,
+        version,
+        vendor: properties.get("java.vendor").cloned(),
+        arch,
+        is_64bit,
+    })
+}
+
+/// Parse the `key = value` lines printed by `-XshowSettings:properties`
+///
+/// Continuation lines of multi-value properties are skipped.
+fn parse_java_properties(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().split_once(" = "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Map `os.arch` values onto Rust's architecture names
+fn normalize_arch(arch: &str) -> String {
+    match arch {
+        "amd64" | "x86_64" => "x86_64",
+        "aarch64" | "arm64" => "aarch64",
+        "x86" | "i386" | "i486" | "i586" | "i686" => "x86",
+        other => other,
     }
+    .to_string()
 }
 
 /// Parse Java version from version output
 fn parse_java_version(version_output: &str) -> Option<JavaVersion> {
-    // Java version output format varies, but we look for patterns like:
-    // "1.8.0_333" (Java 8)
-    // "11.0.16" (Java 11+)
-    // "openjdk version "17.0.4""
-
-    for line in version_output.lines() {
-        if line.contains("version") {
-            // Extract version string from quotes
-            if let Some(start) = line.find('"') {
-                if let Some(end) = line[start + 1..].find('"') {
-                    let version_str = &line[start + 1..start + 1 + end];
-
-                    // Parse different version formats
-                    if version_str.starts_with("1.") {
-                        // Legacy format like "1.8.0_333"
-                        let parts: Vec<&str> = version_str.split('.').collect();
-                        if parts.len() >= 3 {
-                            if let Ok(minor) = parts[1].parse::<u32>() {
-                                return Some(JavaVersion {
-                                    major: minor, // In "1.8", the actual version is 8
-                                    minor: 0,
-                                    patch: 0,
-                                });
-                            }
-                        }
-                    } else {
-                        // Modern format like "17.0.4"
-                        let parts: Vec<&str> = version_str.split('.').collect();
-                        if !parts.is_empty() {
-                            if let Ok(major) = parts[0].parse::<u32>() {
-                                let minor = if parts.len() > 1 {
-                                    parts[1].parse().unwrap_or(0)
-                                } else {
-                                    0
-                                };
-                                let patch = if parts.len() > 2 {
-                                    parts[2].parse().unwrap_or(0)
-                                } else {
-                                    0
-                                };
-
-                                return Some(JavaVersion {
-                                    major,
-                                    minor,
-                                    patch,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    parse_version_string(quoted_version(version_output)?)
+}
+
+/// The quoted version from a line like `openjdk version "17.0.4"`
+fn quoted_version(version_output: &str) -> Option<&str> {
+    version_output
+        .lines()
+        .filter(|line| line.contains("version"))
+        .find_map(|line| {
+            let start = line.find('"')?;
+            let end = line[start + 1..].find('"')?;
+            Some(&line[start + 1..start + 1 + end])
+        })
+}
+
+/// Parse a Java version string like `1.8.0_333` or `17.0.4`
+fn parse_version_string(version_str: &str) -> Option<JavaVersion> {
+    let parts: Vec<&str> = version_str.split(['.', '-', '+', '_']).collect();
+
+    if version_str.starts_with("1.") {
+        // Legacy format like "1.8.0_333", where the actual version is 8
+        let major = parts.get(1)?.parse().ok()?;
+        return Some(JavaVersion {
+            major,
+            minor: 0,
+            patch: 0,
+        });
     }
 
-    None
+    // Modern format like "17.0.4"
+    let major = parts.first()?.parse().ok()?;
+    let minor = parts.get(1).and_then(|p| p.parse().ok()).unwrap_or(0);
+    let patch = parts.get(2).and_then(|p| p.parse().ok()).unwrap_or(0);
+    Some(JavaVersion {
+        major,
+        minor,
+        patch,
+    })
 }
 
 #[allow(clippy::match_same_arms)]
@@ -365,26 +824,26 @@ fn parse_minecraft_version(version: &str) -> Option<(u32, u32)> {
     // Handle versions like "1.20.4", "1.21", "24w14a" (snapshots)
     if version.contains('w') {
         // Snapshot format like "24w14a" - extract year and map to approximate release
-        if let Some(year_str) = version.get(0..2) {
-            if let Ok(year) = year_str.parse::<u32>() {
-                // Map snapshot years to Minecraft versions (approximate)
-                let major = 1;
-                let minor = match year {
-                    24 => 21, // 2024 snapshots are around 1.21
-                    23 => 20, // 2023 snapshots are around 1.20
-                    22 => 19, // 2022 snapshots are around 1.19
-                    _ => 21,  // Default to recent version
-                };
-                return Some((major, minor));
-            }
+        if let Some(year_str) = version.get(0..2)
+            && let Ok(year) = year_str.parse::<u32>()
+        {
+            // Map snapshot years to Minecraft versions (approximate)
+            let major = 1;
+            let minor = match year {
+                24 => 21, // 2024 snapshots are around 1.21
+                23 => 20, // 2023 snapshots are around 1.20
+                22 => 19, // 2022 snapshots are around 1.19
+                _ => 21,  // Default to recent version
+            };
+            return Some((major, minor));
         }
     } else {
         // Regular version format
         let parts: Vec<&str> = version.split('.').collect();
-        if parts.len() >= 2 {
-            if let (Ok(major), Ok(minor)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
-                return Some((major, minor));
-            }
+        if parts.len() >= 2
+            && let (Ok(major), Ok(minor)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>())
+        {
+            return Some((major, minor));
         }
     }
 
@@ -398,8 +857,28 @@ impl Default for JavaManager {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use crate::launcher::test_support::version_info;
+
+    #[test]
+    fn test_parse_java_home_listing() {
+        let listing = "Matching Java Virtual Machines (2):
+    21.0.1 (arm64) \"Eclipse Adoptium\" - \"OpenJDK 21.0.1\" /Library/Java/JavaVirtualMachines/temurin-21.jdk/Contents/Home
+    1.8.0_392 (x86_64) \"Azul Systems, Inc.\" - \"Zulu 8\" /Users/me/Library/Java/JavaVirtualMachines/zulu-8.jdk/Contents/Home
+/Library/Java/JavaVirtualMachines/temurin-21.jdk/Contents/Home
+";
+        assert_eq!(
+            parse_java_home_listing(listing),
+            [
+                PathBuf::from("/Library/Java/JavaVirtualMachines/temurin-21.jdk/Contents/Home"),
+                PathBuf::from(
+                    "/Users/me/Library/Java/JavaVirtualMachines/zulu-8.jdk/Contents/Home"
+                ),
+            ]
+        );
+    }
 
     #[test]
     fn test_parse_java_version() {
@@ -434,6 +913,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_java_installation_from_properties() {
+        let output = "Property settings:
+    file.encoding = UTF-8
+    java.library.path = /usr/java/packages/lib
+        /usr/lib/x86_64-linux-gnu/jni
+    java.vendor = Eclipse Adoptium
+    java.version = 17.0.8
+    os.arch = amd64
+    sun.arch.data.model = 64
+
+openjdk version \"17.0.8\" 2023-07-18
+";
+        let installation =
+            parse_java_installation(Path::new("/opt/jdk17/bin/java"), output).unwrap();
+        assert_eq!(installation.major_version, 17);
+        assert_eq!(installation.version, "17.0.8");
+        assert_eq!(installation.vendor.as_deref(), Some("Eclipse Adoptium"));
+        assert_eq!(installation.arch.as_deref(), Some("x86_64"));
+        assert!(installation.is_64bit);
+
+        let legacy =
+            "    java.version = 1.8.0_382\n    os.arch = i386\n    sun.arch.data.model = 32\n";
+        let installation = parse_java_installation(Path::new("java"), legacy).unwrap();
+        assert_eq!(installation.major_version, 8);
+        assert_eq!(installation.arch.as_deref(), Some("x86"));
+        assert!(!installation.is_64bit);
+
+        // JVMs that reject -XshowSettings only print the version line
+        let plain = r#"openjdk version "21.0.1" 2023-10-17"#;
+        let installation = parse_java_installation(Path::new("java"), plain).unwrap();
+        assert_eq!(installation.major_version, 21);
+        assert_eq!(installation.version, "21.0.1");
+        assert_eq!(installation.vendor, None);
+    }
+
+    #[test]
+    fn test_best_for_major_prefers_native_64bit() {
+        let installation = |path: &str, arch: &str, is_64bit: bool| JavaInstallation {
+            path: PathBuf::from(path),
+            major_version: 17,
+            version: "17.0.8".to_string(),
+            vendor: None,
+            arch: Some(arch.to_string()),
+            is_64bit,
+        };
+        let foreign_arch = if env::consts::ARCH == "x86_64" {
+            "aarch64"
+        } else {
+            "x86_64"
+        };
+
+        let mut manager = JavaManager::new();
+        manager.register(installation("/jdk/foreign", foreign_arch, true));
+        manager.register(installation("/jdk/32bit", env::consts::ARCH, false));
+        manager.register(installation("/jdk/native", env::consts::ARCH, true));
+        manager.register(installation("/jdk/native", env::consts::ARCH, true));
+
+        assert_eq!(manager.installations.len(), 3);
+        assert_eq!(
+            manager.best_for_major(17).unwrap().path,
+            PathBuf::from("/jdk/native")
+        );
+        assert!(manager.best_for_major(21).is_none());
+    }
+
     #[test]
     fn test_parse_minecraft_version() {
         assert_eq!(parse_minecraft_version("1.20.4"), Some((1, 20)));
@@ -451,5 +996,37 @@ mod tests {
         assert_eq!(JavaManager::get_required_java_version("1.16.5"), 11);
         assert_eq!(JavaManager::get_required_java_version("1.15.2"), 8);
         assert_eq!(JavaManager::get_required_java_version("24w14a"), 21);
+        assert_eq!(JavaManager::get_required_java_version("25.1"), 21);
+    }
+
+    #[test]
+    fn test_required_java_prefers_version_json() {
+        let snapshot = version_info(serde_json::json!({
+            "id": "24w14a",
+            "javaVersion": { "component": "java-runtime-delta", "majorVersion": 21 },
+        }));
+        let requirement = JavaManager::required_java(&snapshot);
+        assert_eq!(requirement.major, 21);
+        assert_eq!(requirement.source, JavaRequirementSource::VersionJson);
+        assert!(requirement.allows(25));
+
+        let april_fools = version_info(serde_json::json!({
+            "id": "3D Shareware v1.34",
+            "javaVersion": { "component": "jre-legacy", "majorVersion": 8 },
+        }));
+        assert_eq!(JavaManager::required_java(&april_fools).major, 8);
+    }
+
+    #[test]
+    fn test_required_java_caps_launchwrapper_versions() {
+        let legacy = version_info(serde_json::json!({
+            "id": "1.5.2",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+        }));
+        let requirement = JavaManager::required_java(&legacy);
+        assert_eq!(requirement.major, 8);
+        assert_eq!(requirement.max_major, Some(8));
+        assert_eq!(requirement.source, JavaRequirementSource::VersionTable);
+        assert!(!requirement.allows(17));
     }
 }
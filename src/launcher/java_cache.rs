@@ -0,0 +1,238 @@
+//! On-disk state for Java discovery
+//!
+//! Probing an installation means spawning `java`, so results are cached per
+//! executable path together with its modification time and reused until the
+//! executable changes. The macOS `java_home` listing is cached the same way,
+//! keyed by the modification time of the JVM directories it reads. Java
+//! installs registered with `java add` live in a
+//! separate file because, unlike the cache, they must never be thrown away.
+
+use crate::error::{Result, ResultExt};
+use crate::fs_utils::{self, FileLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, warn};
+
+use crate::launcher::java::JavaInstallation;
+
+/// Cached result of probing one java executable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ProbeCacheEntry {
+    /// Modification time of the executable when it was probed
+    modified: SystemTime,
+    installation: JavaInstallation,
+}
+
+/// Cached Java homes reported by macOS `java_home`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct JavaHomesEntry {
+    /// Newest modification time of the JVM directories when listed
+    modified: Option<SystemTime>,
+    homes: Vec<PathBuf>,
+}
+
+/// Layout of the cache file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    probes: HashMap<PathBuf, ProbeCacheEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    java_homes: Option<JavaHomesEntry>,
+}
+
+/// Probe results keyed by executable path
+#[derive(Debug, Default)]
+pub struct ProbeCache {
+    file: CacheFile,
+    /// Paths looked up since loading, everything else is pruned on save
+    used: HashSet<PathBuf>,
+    dirty: bool,
+}
+
+impl ProbeCache {
+    /// Load the cache, starting empty if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        let file: CacheFile = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable Java cache {}: {e}", path.display());
+                CacheFile::default()
+            }),
+            Err(_) => CacheFile::default(),
+        };
+
+        Self {
+            file,
+            ..Self::default()
+        }
+    }
+
+    /// Cached `java_home` listing, if none of `jvm_dirs` changed since
+    pub fn java_homes(&self, jvm_dirs: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        let modified = newest_modified_time(jvm_dirs);
+        self.file
+            .java_homes
+            .as_ref()
+            .filter(|entry| entry.modified == modified)
+            .map(|entry| entry.homes.clone())
+    }
+
+    /// Remember a fresh `java_home` listing for `jvm_dirs`
+    pub fn set_java_homes(&mut self, jvm_dirs: &[PathBuf], homes: Vec<PathBuf>) {
+        let entry = JavaHomesEntry {
+            modified: newest_modified_time(jvm_dirs),
+            homes,
+        };
+        if self.file.java_homes.as_ref() != Some(&entry) {
+            self.file.java_homes = Some(entry);
+            self.dirty = true;
+        }
+    }
+
+    /// Cached installation for `java_path`, if the executable has not changed since
+    pub fn get(&mut self, java_path: &Path) -> Option<JavaInstallation> {
+        let modified = modified_time(java_path)?;
+        let entry = self.file.probes.get(java_path)?;
+        if entry.modified != modified {
+            debug!("Java cache entry is stale: {}", java_path.display());
+            return None;
+        }
+
+        self.used.insert(java_path.to_path_buf());
+        Some(entry.installation.clone())
+    }
+
+    /// Remember a fresh probe result
+    pub fn insert(&mut self, java_path: &Path, installation: &JavaInstallation) {
+        let Some(modified) = modified_time(java_path) else {
+            return;
+        };
+        let entry = ProbeCacheEntry {
+            modified,
+            installation: installation.clone(),
+        };
+
+        self.used.insert(java_path.to_path_buf());
+        if self.file.probes.get(java_path) != Some(&entry) {
+            self.file.probes.insert(java_path.to_path_buf(), entry);
+            self.dirty = true;
+        }
+    }
+
+    /// Forget every cached probe so the next scan starts over
+    pub fn clear(&mut self) {
+        self.dirty |= !self.file.probes.is_empty() || self.file.java_homes.is_some();
+        self.file.probes.clear();
+        self.file.java_homes = None;
+        self.used.clear();
+    }
+
+    /// Write the cache if it changed, dropping entries that were not used
+    pub async fn save(&mut self, path: &Path) -> Result<()> {
+        let before = self.file.probes.len();
+        let used = &self.used;
+        self.file
+            .probes
+            .retain(|java_path, _| used.contains(java_path));
+        if !self.dirty && self.file.probes.len() == before {
+            return Ok(());
+        }
+
+        let content =
+            serde_json::to_string_pretty(&self.file).context("Failed to serialize Java cache")?;
+        fs_utils::write_atomic(path, content)
+            .await
+            .context("Failed to write Java cache")?;
+        self.dirty = false;
+
+        Ok(())
+    }
+}
+
+/// Modification time of the file a path points at
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Newest modification time among `paths`, ignoring ones that do not exist
+fn newest_modified_time(paths: &[PathBuf]) -> Option<SystemTime> {
+    paths.iter().filter_map(|path| modified_time(path)).max()
+}
+
+/// Load the java executables registered with `java add`
+pub fn load_user_java_paths(path: &Path) -> Vec<PathBuf> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable Java registry {}: {e}", path.display());
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Change the registered java executables under a lock, returning the closure's result
+pub async fn update_user_java_paths<T>(
+    path: &Path,
+    update: impl FnOnce(&mut Vec<PathBuf>) -> T,
+) -> Result<T> {
+    let _lock = FileLock::exclusive_async(&fs_utils::sibling_path(path, ".lock")).await?;
+
+    let mut paths = load_user_java_paths(path);
+    let result = update(&mut paths);
+
+    let content =
+        serde_json::to_string_pretty(&paths).context("Failed to serialize Java registry")?;
+    fs_utils::write_atomic(path, content)
+        .await
+        .context("Failed to write Java registry")?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+
+    fn installation(path: &Path) -> JavaInstallation {
+        JavaInstallation {
+            path: path.to_path_buf(),
+            major_version: 17,
+            version: "17.0.8".to_string(),
+            vendor: None,
+            arch: Some(std::env::consts::ARCH.to_string()),
+            is_64bit: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_cache_invalidates_on_mtime_change() {
+        let dir = test_support::temp_dir("java-cache");
+        std::fs::create_dir_all(&dir).unwrap();
+        let java = dir.join("java");
+        let unused = dir.join("old-java");
+        std::fs::write(&java, "v1").unwrap();
+        std::fs::write(&unused, "v1").unwrap();
+        let cache_path = dir.join("java_cache.json");
+
+        let mut cache = ProbeCache::load(&cache_path);
+        cache.insert(&java, &installation(&java));
+        cache.insert(&unused, &installation(&unused));
+        cache.save(&cache_path).await.unwrap();
+
+        // Entries survive a reload, and ones not looked up are pruned on save
+        let mut cache = ProbeCache::load(&cache_path);
+        assert_eq!(cache.get(&java).unwrap().major_version, 17);
+        cache.save(&cache_path).await.unwrap();
+        let mut cache = ProbeCache::load(&cache_path);
+        assert!(cache.get(&unused).is_none());
+
+        // Touching the executable invalidates its entry
+        let file = std::fs::File::options().write(true).open(&java).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        assert!(cache.get(&java).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Named sets of garbage collector flags that can be selected per instance
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JvmPreset {
+    /// G1 tuned like the official launcher
+    #[default]
+    Default,
+    /// Aikar's G1 flags (<https://docs.papermc.io/paper/aikars-flags>)
+    Aikar,
+    /// Z Garbage Collector, low pause times on Java 11+
+    Zgc,
+    /// Shenandoah, low pause times on `OpenJDK` 12+
+    Shenandoah,
+    /// No GC flags at all, leave everything to the JVM
+    Minimal,
+}
+
+/// G1 flags used by the official launcher
+const DEFAULT_G1_FLAGS: &[&str] = &[
+    "-XX:+UseG1GC",
+    "-XX:+UnlockExperimentalVMOptions",
+    "-XX:G1NewSizePercent=20",
+    "-XX:G1ReservePercent=20",
+    "-XX:MaxGCPauseMillis=50",
+    "-XX:G1HeapRegionSize=32M",
+];
+
+/// G1 flags that are product (non-experimental) options on Java 8
+const JAVA8_G1_FLAGS: &[&str] = &[
+    "-XX:+UseG1GC",
+    "-XX:G1ReservePercent=20",
+    "-XX:MaxGCPauseMillis=50",
+    "-XX:G1HeapRegionSize=32M",
+];
+
+const AIKAR_FLAGS: &[&str] = &[
+    "-XX:+UseG1GC",
+    "-XX:+ParallelRefProcEnabled",
+    "-XX:MaxGCPauseMillis=200",
+    "-XX:+UnlockExperimentalVMOptions",
+    "-XX:+DisableExplicitGC",
+    "-XX:+AlwaysPreTouch",
+    "-XX:G1NewSizePercent=30",
+    "-XX:G1MaxNewSizePercent=40",
+    "-XX:G1HeapRegionSize=8M",
+    "-XX:G1ReservePercent=20",
+    "-XX:G1HeapWastePercent=5",
+    "-XX:G1MixedGCCountTarget=4",
+    "-XX:InitiatingHeapOccupancyPercent=15",
+    "-XX:G1MixedGCLiveThresholdPercent=90",
+    "-XX:G1RSetUpdatingPauseTimePercent=5",
+    "-XX:SurvivorRatio=32",
+    "-XX:+PerfDisableSharedMem",
+    "-XX:MaxTenuringThreshold=1",
+    "-Dusing.aikars.flags=https://mcflags.emc.gs",
+    "-Daikars.new.flags=true",
+];
+
+impl JvmPreset {
+    /// GC flags for this preset on the given Java major version
+    ///
+    /// Presets that the JVM cannot run fall back to the default G1 flags.
+    pub fn flags(self, java_major: u32) -> Vec<&'static str> {
+        match self {
+            Self::Default => default_flags(java_major),
+            Self::Aikar => AIKAR_FLAGS.to_vec(),
+            Self::Zgc => match java_major {
+                // ZGC became a product feature in Java 15
+                15.. => {
+                    let mut flags = vec!["-XX:+UseZGC"];
+                    // Generational ZGC is opt-in on 21 and 22, and the only mode from 23
+                    if (21..23).contains(&java_major) {
+                        flags.push("-XX:+ZGenerational");
+                    }
+                    flags
+                }
+                11..=14 => vec!["-XX:+UnlockExperimentalVMOptions", "-XX:+UseZGC"],
+                _ => {
+                    warn!(
+                        "ZGC requires Java 11 or newer, using default GC flags on Java {java_major}"
+                    );
+                    default_flags(java_major)
+                }
+            },
+            Self::Shenandoah => match java_major {
+                // Shenandoah became a product feature in Java 15
+                15.. => vec!["-XX:+UseShenandoahGC"],
+                12..=14 => vec!["-XX:+UnlockExperimentalVMOptions", "-XX:+UseShenandoahGC"],
+                _ => {
+                    warn!(
+                        "Shenandoah requires Java 12 or newer, using default GC flags on Java {java_major}"
+                    );
+                    default_flags(java_major)
+                }
+            },
+            Self::Minimal => Vec::new(),
+        }
+    }
+}
+
+/// Default G1 flags, without the experimental tuning options Java 8 builds may reject
+fn default_flags(java_major: u32) -> Vec<&'static str> {
+    if java_major <= 8 {
+        JAVA8_G1_FLAGS.to_vec()
+    } else {
+        DEFAULT_G1_FLAGS.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_preset_avoids_experimental_flags_on_java8() {
+        let flags = JvmPreset::Default.flags(8);
+        assert!(flags.contains(&"-XX:+UseG1GC"));
+        assert!(!flags.contains(&"-XX:+UnlockExperimentalVMOptions"));
+        assert!(
+            JvmPreset::Default
+                .flags(17)
+                .contains(&"-XX:G1NewSizePercent=20")
+        );
+    }
+
+    #[test]
+    fn test_low_pause_presets_depend_on_java_version() {
+        assert_eq!(JvmPreset::Zgc.flags(17), vec!["-XX:+UseZGC"]);
+        assert_eq!(
+            JvmPreset::Zgc.flags(21),
+            vec!["-XX:+UseZGC", "-XX:+ZGenerational"]
+        );
+        assert!(
+            JvmPreset::Zgc
+                .flags(11)
+                .contains(&"-XX:+UnlockExperimentalVMOptions")
+        );
+        assert_eq!(JvmPreset::Zgc.flags(8), JvmPreset::Default.flags(8));
+        assert_eq!(
+            JvmPreset::Shenandoah.flags(21),
+            vec!["-XX:+UseShenandoahGC"]
+        );
+        assert!(JvmPreset::Minimal.flags(21).is_empty());
+    }
+}
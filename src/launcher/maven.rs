@@ -0,0 +1,247 @@
+//! Maven coordinates of libraries
+//!
+//! Version JSONs name libraries `group:artifact:version[:classifier][@extension]`.
+//! The coordinate decides where a library lives in the `libraries` directory
+//! and, for loader profiles that only give a repository `url`, where it is
+//! downloaded from. Timestamped snapshots such as `1.0-20240101.120000-3`
+//! are stored under their `1.0-SNAPSHOT` directory, as in a Maven repository.
+
+use crate::error::{GameError, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Extension used when a coordinate does not name one
+const DEFAULT_EXTENSION: &str = "jar";
+
+/// A parsed `group:artifact:version[:classifier][@extension]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MavenCoordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+    pub extension: String,
+}
+
+impl MavenCoordinate {
+    /// Parse a library name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group, artifact or version is missing or empty,
+    /// or if any part could lead outside the `libraries` directory.
+    pub fn parse(name: &str) -> Result<Self> {
+        let invalid = || {
+            GameError::libraries_download(format!(
+                "Invalid library name '{name}', expected group:artifact:version[:classifier][@extension]"
+            ))
+        };
+
+        let (coordinate, extension) = match name.rsplit_once('@') {
+            Some((coordinate, extension)) if !extension.is_empty() => (coordinate, extension),
+            Some(_) => return Err(invalid().into()),
+            None => (name, DEFAULT_EXTENSION),
+        };
+
+        let parts: Vec<&str> = coordinate.split(':').collect();
+        // Every part becomes a path component, the group one per dot-separated segment
+        let group_segments = parts.first().into_iter().flat_map(|group| group.split('.'));
+        if !parts
+            .iter()
+            .copied()
+            .chain(group_segments)
+            .chain([extension])
+            .all(is_path_safe)
+        {
+            return Err(invalid().into());
+        }
+        let (group, artifact, version, classifier) = match parts.as_slice() {
+            [group, artifact, version] => (group, artifact, version, None),
+            [group, artifact, version, classifier] => {
+                (group, artifact, version, Some((*classifier).to_string()))
+            }
+            _ => return Err(invalid().into()),
+        };
+
+        Ok(Self {
+            group: (*group).to_string(),
+            artifact: (*artifact).to_string(),
+            version: (*version).to_string(),
+            classifier,
+            extension: extension.to_string(),
+        })
+    }
+
+    /// The same artifact with another classifier, e.g. a legacy native
+    #[must_use]
+    pub fn with_classifier(&self, classifier: &str) -> Self {
+        Self {
+            classifier: Some(classifier.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// `group:artifact[:classifier]`, which identifies a library across versions
+    pub fn key(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}:{}:{classifier}", self.group, self.artifact),
+            None => format!("{}:{}", self.group, self.artifact),
+        }
+    }
+
+    /// Directory version, `1.0-SNAPSHOT` for a timestamped `1.0-20240101.120000-3`
+    pub fn base_version(&self) -> String {
+        let mut parts = self.version.rsplitn(3, '-');
+        let (Some(build), Some(timestamp), Some(base)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return self.version.clone();
+        };
+
+        let is_timestamp = timestamp.split_once('.').is_some_and(|(date, time)| {
+            date.len() == 8
+                && time.len() == 6
+                && date.bytes().chain(time.bytes()).all(|b| b.is_ascii_digit())
+        });
+        if is_timestamp && !build.is_empty() && build.bytes().all(|b| b.is_ascii_digit()) {
+            format!("{base}-SNAPSHOT")
+        } else {
+            self.version.clone()
+        }
+    }
+
+    /// File name, e.g. `lwjgl-3.3.3-natives-linux.jar`
+    pub fn file_name(&self) -> String {
+        let classifier = self
+            .classifier
+            .as_ref()
+            .map(|classifier| format!("-{classifier}"))
+            .unwrap_or_default();
+        format!(
+            "{}-{}{classifier}.{}",
+            self.artifact, self.version, self.extension
+        )
+    }
+
+    /// Path relative to a repository root or the `libraries` directory
+    ///
+    /// Example: `org.lwjgl:lwjgl:3.3.3:natives-linux` ->
+    /// `org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux.jar`
+    pub fn path(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.group.replace('.', "/"),
+            self.artifact,
+            self.base_version(),
+            self.file_name()
+        )
+    }
+
+    /// Download URL in the Maven repository at `repository`
+    pub fn url(&self, repository: &str) -> String {
+        format!("{}/{}", repository.trim_end_matches('/'), self.path())
+    }
+}
+
+/// Whether a coordinate part is usable as a single path component
+fn is_path_safe(part: &str) -> bool {
+    !part.is_empty() && part != "." && part != ".." && !part.contains(['/', '\\'])
+}
+
+impl fmt::Display for MavenCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{classifier}")?;
+        }
+        if self.extension != DEFAULT_EXTENSION {
+            write!(f, "@{}", self.extension)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MavenCoordinate {
+    type Err = crate::error::RustifiedError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::parse(name)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_round_trip() {
+        for name in [
+            "com.mojang:brigadier:1.0.18",
+            "org.lwjgl:lwjgl-freetype:3.3.3:natives-macos-arm64",
+            "de.oceanlabs.mcp:mcp_config:1.20.4-20231207.154220@zip",
+            "net.minecraftforge:forge:1.20.4-49.0.3:client@txt",
+            "net.fabricmc:sponge-mixin:0.12.5+mixin.0.8.5",
+        ] {
+            assert_eq!(MavenCoordinate::parse(name).unwrap().to_string(), name);
+        }
+        // An explicit `@jar` is the default and is dropped
+        assert_eq!(
+            MavenCoordinate::parse("org.ow2.asm:asm:9.6@jar")
+                .unwrap()
+                .to_string(),
+            "org.ow2.asm:asm:9.6"
+        );
+
+        for name in [
+            "",
+            "org.ow2.asm:asm",
+            "org.ow2.asm::9.6",
+            "a:b:c:d:e",
+            "a:b:c@",
+            "org.ow2.asm:..:9.6",
+            "org..ow2:asm:9.6",
+            "..:asm:9.6",
+            "org.ow2.asm:asm:../../9.6",
+            "org.ow2.asm:asm:9.6:..\\natives",
+            "org.ow2.asm:asm:9.6@/jar",
+            "/etc:asm:9.6",
+        ] {
+            assert!(MavenCoordinate::parse(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_paths_and_urls() {
+        let coordinate = MavenCoordinate::parse("org.lwjgl:lwjgl:3.3.3").unwrap();
+        assert_eq!(coordinate.path(), "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar");
+        assert_eq!(
+            coordinate.with_classifier("natives-linux").path(),
+            "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux.jar"
+        );
+        assert_eq!(coordinate.key(), "org.lwjgl:lwjgl");
+
+        let zip = MavenCoordinate::parse("de.oceanlabs.mcp:mcp_config:1.20.4@zip").unwrap();
+        assert_eq!(
+            zip.path(),
+            "de/oceanlabs/mcp/mcp_config/1.20.4/mcp_config-1.20.4.zip"
+        );
+
+        let fabric = MavenCoordinate::parse("net.fabricmc:fabric-loader:0.15.7").unwrap();
+        assert_eq!(
+            fabric.url("https://maven.fabricmc.net/"),
+            "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.7/fabric-loader-0.15.7.jar"
+        );
+
+        let snapshot =
+            MavenCoordinate::parse("org.spongepowered:mixin:0.8.6-20240101.120000-3").unwrap();
+        assert_eq!(
+            snapshot.path(),
+            "org/spongepowered/mixin/0.8.6-SNAPSHOT/mixin-0.8.6-20240101.120000-3.jar"
+        );
+        let plain = MavenCoordinate::parse("org.spongepowered:mixin:0.8.6-SNAPSHOT").unwrap();
+        assert_eq!(plain.base_version(), "0.8.6-SNAPSHOT");
+        // Forge versions contain dashes but are not snapshots
+        let forge = MavenCoordinate::parse("net.minecraftforge:forge:1.20.4-49.0.3").unwrap();
+        assert_eq!(forge.base_version(), "1.20.4-49.0.3");
+    }
+}
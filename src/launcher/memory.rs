@@ -0,0 +1,394 @@
+//! Heap size of the game's JVM
+//!
+//! Instances set the initial (`-Xms`) and maximum (`-Xmx`) heap either in
+//! megabytes or as a percentage of physical memory, which is read from
+//! `/proc/meminfo` on Linux. `-Xms`/`-Xmx` in an instance's own Java
+//! arguments take precedence over these settings.
+
+use crate::error::{InstanceError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::warn;
+
+/// Largest heap an instance may ask for (128 GB)
+pub const MAX_MEMORY_MB: u32 = 128 * 1024;
+
+/// Heap used when an instance does not configure one
+const DEFAULT_MIN_MB: u32 = 1024;
+const DEFAULT_MAX_MB: u32 = 2048;
+
+/// Largest heap a 32-bit JVM can reliably reserve
+const MAX_32BIT_HEAP_MB: u32 = 1536;
+
+/// A memory setting, either absolute or relative to physical memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum MemorySize {
+    Megabytes(u32),
+    /// Percentage of physical memory
+    Percent(u8),
+}
+
+impl MemorySize {
+    /// Parse `4096`, `4096M`, `4G`, `4GB` or `50%`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size is malformed, zero, above 128 GB or not a
+    /// percentage between 1 and 100.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let invalid = || {
+            InstanceError::invalid_config(format!(
+                "Invalid memory size '{spec}', expected e.g. 4096, 512M, 4G or 50%"
+            ))
+        };
+
+        if let Some(percent) = spec.strip_suffix('%') {
+            let percent: u8 = percent.trim().parse().map_err(|_| invalid())?;
+            if !(1..=100).contains(&percent) {
+                return Err(InstanceError::invalid_config(format!(
+                    "Memory percentage must be between 1% and 100%, got {spec}"
+                ))
+                .into());
+            }
+            return Ok(Self::Percent(percent));
+        }
+
+        let upper = spec.to_ascii_uppercase();
+        let (number, multiplier) =
+            if let Some(gigabytes) = upper.strip_suffix("GB").or_else(|| upper.strip_suffix('G')) {
+                (gigabytes, 1024)
+            } else {
+                let megabytes = upper
+                    .strip_suffix("MB")
+                    .or_else(|| upper.strip_suffix('M'))
+                    .unwrap_or(&upper);
+                (megabytes, 1)
+            };
+        let megabytes = number
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .and_then(|value| value.checked_mul(multiplier))
+            .ok_or_else(invalid)?;
+
+        if megabytes == 0 {
+            return Err(InstanceError::invalid_config(
+                "Memory must be greater than 0 MB".to_string(),
+            )
+            .into());
+        }
+        if megabytes > MAX_MEMORY_MB {
+            return Err(InstanceError::invalid_config(format!(
+                "Memory value too large ({megabytes} MB). Maximum allowed is {MAX_MEMORY_MB} MB (128 GB)"
+            ))
+            .into());
+        }
+        Ok(Self::Megabytes(megabytes))
+    }
+
+    /// Size in megabytes, `None` for a percentage when physical memory is unknown
+    pub fn resolve(self, total_mb: Option<u64>) -> Option<u32> {
+        match self {
+            Self::Megabytes(megabytes) => Some(megabytes),
+            Self::Percent(percent) => total_mb.map(|total| {
+                u32::try_from(total * u64::from(percent) / 100)
+                    .unwrap_or(u32::MAX)
+                    .max(1)
+            }),
+        }
+    }
+}
+
+impl fmt::Display for MemorySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Megabytes(megabytes) if megabytes % 1024 == 0 => {
+                write!(f, "{}G", megabytes / 1024)
+            }
+            Self::Megabytes(megabytes) => write!(f, "{megabytes}M"),
+            Self::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+impl TryFrom<String> for MemorySize {
+    type Error = crate::error::RustifiedError;
+
+    fn try_from(spec: String) -> Result<Self> {
+        Self::parse(&spec)
+    }
+}
+
+impl From<MemorySize> for String {
+    fn from(size: MemorySize) -> Self {
+        size.to_string()
+    }
+}
+
+/// Initial and maximum heap of one launch, in megabytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapSize {
+    pub min_mb: u32,
+    pub max_mb: u32,
+}
+
+impl HeapSize {
+    /// Resolve an instance's memory settings against the machine's physical memory
+    ///
+    /// Unset or unresolvable sizes fall back to 1 GB initial and 2 GB maximum,
+    /// and the initial heap never exceeds the maximum.
+    pub fn resolve(
+        min: Option<MemorySize>,
+        max: Option<MemorySize>,
+        total_mb: Option<u64>,
+    ) -> Self {
+        let resolve = |size: Option<MemorySize>| {
+            let size = size?;
+            let resolved = size.resolve(total_mb);
+            if resolved.is_none() {
+                warn!(
+                    "Cannot detect the system's memory to apply {size}, using the default heap size"
+                );
+            }
+            resolved
+        };
+
+        let max_mb = resolve(max).unwrap_or(DEFAULT_MAX_MB);
+        let min_mb = match resolve(min) {
+            Some(min_mb) if min_mb > max_mb => {
+                warn!(
+                    "Initial heap of {min_mb}MB is larger than the maximum of {max_mb}MB, using {max_mb}MB"
+                );
+                max_mb
+            }
+            Some(min_mb) => min_mb,
+            None => DEFAULT_MIN_MB.min(max_mb),
+        };
+        Self { min_mb, max_mb }
+    }
+
+    /// Warn about a maximum heap the machine or the JVM cannot provide
+    pub fn check(self, total_mb: Option<u64>, is_64bit: bool) {
+        if let Some(total_mb) = total_mb
+            && u64::from(self.max_mb) > total_mb
+        {
+            warn!(
+                "Maximum heap of {}MB is more than the {total_mb}MB of physical memory, the game may swap or be killed",
+                self.max_mb
+            );
+        }
+        if !is_64bit && self.max_mb > MAX_32BIT_HEAP_MB {
+            warn!(
+                "A 32-bit Java can usually not reserve more than {MAX_32BIT_HEAP_MB}MB of heap, {}MB will likely fail to start; install a 64-bit Java",
+                self.max_mb
+            );
+        }
+    }
+
+    /// The `-Xms` and `-Xmx` flags
+    pub fn flags(self) -> [String; 2] {
+        [
+            format!("-Xms{}M", self.min_mb),
+            format!("-Xmx{}M", self.max_mb),
+        ]
+    }
+}
+
+/// The heap of a launch after the user's own Java arguments are applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapArguments {
+    /// The heap the JVM will use, as far as the flags can be read
+    pub heap: HeapSize,
+    /// The `-Xms` and `-Xmx` flags
+    pub flags: [String; 2],
+    /// The user's other Java arguments
+    pub others: Vec<String>,
+}
+
+/// The heap flags of a launch and the user's other Java arguments
+///
+/// `-Xms`/`-Xmx` in `java_args` replace the flags from `heap`; when one is
+/// repeated only the last is kept, which is also the one the JVM would use.
+/// An initial heap above the resulting maximum is lowered to it, since the
+/// JVM refuses to start otherwise.
+pub fn heap_arguments(heap: HeapSize, java_args: &[String]) -> HeapArguments {
+    let mut effective = heap;
+    let [mut min_flag, mut max_flag] = heap.flags();
+
+    for (prefix, flag, size_mb) in [
+        ("-Xms", &mut min_flag, &mut effective.min_mb),
+        ("-Xmx", &mut max_flag, &mut effective.max_mb),
+    ] {
+        let user_flags: Vec<_> = java_args
+            .iter()
+            .filter(|arg| arg.starts_with(prefix))
+            .collect();
+        if let Some(user_flag) = user_flags.last() {
+            if user_flags.len() > 1 {
+                warn!(
+                    "Java arguments contain {} {prefix} flags, using {user_flag}",
+                    user_flags.len()
+                );
+            }
+            warn!("Java argument {user_flag} overrides the instance's memory setting ({flag})");
+            (*flag).clone_from(user_flag);
+            if let Some(user_mb) = jvm_size_mb(&user_flag[prefix.len()..]) {
+                *size_mb = user_mb;
+            } else {
+                warn!("Cannot read the heap size of Java argument {user_flag}");
+            }
+        }
+    }
+
+    if effective.min_mb > effective.max_mb {
+        warn!(
+            "Initial heap {min_flag} is larger than the maximum heap {max_flag}, using {}MB",
+            effective.max_mb
+        );
+        effective.min_mb = effective.max_mb;
+        min_flag = format!("-Xms{}M", effective.max_mb);
+    }
+
+    let others = java_args
+        .iter()
+        .filter(|arg| !arg.starts_with("-Xms") && !arg.starts_with("-Xmx"))
+        .cloned()
+        .collect();
+    HeapArguments {
+        heap: effective,
+        flags: [min_flag, max_flag],
+        others,
+    }
+}
+
+/// Megabytes of a JVM size such as `512m`, `4G` or `1048576k`; bare numbers are bytes
+fn jvm_size_mb(size: &str) -> Option<u32> {
+    let (digits, unit) = match size.chars().last()? {
+        'k' | 'K' => (&size[..size.len() - 1], 1u64 << 10),
+        'm' | 'M' => (&size[..size.len() - 1], 1 << 20),
+        'g' | 'G' => (&size[..size.len() - 1], 1 << 30),
+        't' | 'T' => (&size[..size.len() - 1], 1 << 40),
+        _ => (size, 1),
+    };
+    let bytes = digits.parse::<u64>().ok()?.checked_mul(unit)?;
+    u32::try_from(bytes >> 20).ok()
+}
+
+/// Physical memory of the machine in megabytes, if it can be detected
+pub fn total_memory_mb() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|meminfo| parse_meminfo(&meminfo))
+    } else {
+        None
+    }
+}
+
+/// `MemTotal` from `/proc/meminfo`, converted from kB to MB
+fn parse_meminfo(meminfo: &str) -> Option<u64> {
+    let line = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?;
+    let kilobytes: u64 = line.trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kilobytes / 1024)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_memory_sizes() {
+        assert_eq!(
+            MemorySize::parse("4096").unwrap(),
+            MemorySize::Megabytes(4096)
+        );
+        assert_eq!(
+            MemorySize::parse("512m").unwrap(),
+            MemorySize::Megabytes(512)
+        );
+        assert_eq!(
+            MemorySize::parse("4GB").unwrap(),
+            MemorySize::Megabytes(4096)
+        );
+        assert_eq!(MemorySize::parse(" 50% ").unwrap(), MemorySize::Percent(50));
+        assert!(MemorySize::parse("0").is_err());
+        assert!(MemorySize::parse("150%").is_err());
+        assert!(MemorySize::parse("200G").is_err());
+        assert!(MemorySize::parse("lots").is_err());
+
+        assert_eq!(MemorySize::Megabytes(4096).to_string(), "4G");
+        assert_eq!(MemorySize::Megabytes(1500).to_string(), "1500M");
+        assert_eq!(MemorySize::Percent(25).resolve(Some(16_000)), Some(4000));
+        assert_eq!(MemorySize::Percent(25).resolve(None), None);
+
+        let meminfo = "MemTotal:       16318412 kB\nMemFree:         1234567 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(15935));
+    }
+
+    #[test]
+    fn test_heap_size_defaults_and_clamping() {
+        assert_eq!(
+            HeapSize::resolve(None, None, None),
+            HeapSize {
+                min_mb: 1024,
+                max_mb: 2048
+            }
+        );
+        assert_eq!(
+            HeapSize::resolve(None, Some(MemorySize::Megabytes(512)), None),
+            HeapSize {
+                min_mb: 512,
+                max_mb: 512
+            }
+        );
+        assert_eq!(
+            HeapSize::resolve(
+                Some(MemorySize::Megabytes(8192)),
+                Some(MemorySize::Percent(25)),
+                Some(16384)
+            ),
+            HeapSize {
+                min_mb: 4096,
+                max_mb: 4096
+            }
+        );
+    }
+
+    #[test]
+    fn test_user_heap_flags_replace_settings() {
+        let heap = HeapSize {
+            min_mb: 1024,
+            max_mb: 4096,
+        };
+        let java_args = ["-Xmx2G", "-XX:+UseZGC", "-Xmx3G"].map(String::from);
+        let arguments = heap_arguments(heap, &java_args);
+        assert_eq!(arguments.flags, ["-Xms1024M", "-Xmx3G"]);
+        assert_eq!(arguments.others, ["-XX:+UseZGC"]);
+        assert_eq!(arguments.heap.max_mb, 3072);
+        assert_eq!(heap_arguments(heap, &[]).flags, ["-Xms1024M", "-Xmx4096M"]);
+    }
+
+    #[test]
+    fn test_user_max_heap_below_initial_heap() {
+        let heap = HeapSize::resolve(None, None, None);
+        let arguments = heap_arguments(heap, &["-Xmx512M".to_string()]);
+        assert_eq!(arguments.flags, ["-Xms512M", "-Xmx512M"]);
+        assert_eq!(
+            arguments.heap,
+            HeapSize {
+                min_mb: 512,
+                max_mb: 512
+            }
+        );
+
+        // JVM sizes without a unit are bytes
+        let arguments = heap_arguments(heap, &["-Xms268435456".to_string()]);
+        assert_eq!(arguments.heap.min_mb, 256);
+        assert_eq!(arguments.flags[0], "-Xms268435456");
+    }
+}
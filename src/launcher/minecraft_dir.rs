@@ -66,6 +66,41 @@ impl MinecraftDir {
         self.assets_dir().join("indexes")
     }
 
+    /// Get the directory holding the log4j configs of versions
+    pub fn log_configs_dir(&self) -> PathBuf {
+        self.assets_dir().join("log_configs")
+    }
+
+    /// Get the path for a log4j config file by its id
+    pub fn log_config_path(&self, config_id: &str) -> PathBuf {
+        self.log_configs_dir().join(config_id)
+    }
+
+    /// Get the managed Java runtimes directory path
+    pub fn runtime_dir(&self) -> PathBuf {
+        self.base_path.join("runtime")
+    }
+
+    /// Get the install directory of a managed Java runtime component
+    ///
+    /// Uses the same `runtime/<component>/<platform>/<component>` layout as the official launcher.
+    pub fn java_runtime_dir(&self, component: &str, platform: &str) -> PathBuf {
+        self.runtime_dir()
+            .join(component)
+            .join(platform)
+            .join(component)
+    }
+
+    /// Get the path of the Java discovery cache
+    pub fn java_cache_path(&self) -> PathBuf {
+        self.base_path.join("java_cache.json")
+    }
+
+    /// Get the path of the list of user-registered Java installations
+    pub fn user_java_paths_path(&self) -> PathBuf {
+        self.base_path.join("java_paths.json")
+    }
+
     /// Get the path for a specific version directory
     pub fn version_dir(&self, version_id: &str) -> PathBuf {
         self.versions_dir().join(version_id)
@@ -1,25 +1,48 @@
+mod arguments;
+mod classpath;
+pub mod crash;
+pub mod environment;
 mod files;
 mod game;
+pub mod game_log;
+pub mod history;
+mod hooks;
 mod instance;
 pub mod java;
+mod java_cache;
+pub mod jvm;
+mod maven;
+pub mod memory;
 mod minecraft_dir;
+mod natives;
+mod plan;
+mod process;
+mod rules;
+mod runtime;
+#[cfg(test)]
+pub(crate) mod test_support;
 mod version;
 
-pub use files::{FileManager, get_library_path};
-pub use instance::{InstanceConfig, InstanceManager};
+pub use files::FileManager;
+pub use game::{GameSession, LaunchMode, LaunchOptions, LaunchOutcome, QuickPlay};
+pub use instance::{InstanceConfig, InstanceManager, ModLoader, ServerConfig};
 pub use java::JavaManager;
 pub use minecraft_dir::MinecraftDir;
-pub use version::VersionType;
+pub use plan::LaunchPlan;
+pub use version::{VersionInfo, VersionType};
 
-use crate::error::Result;
+use crate::error::{Result, ResultExt};
+use crate::fs_utils::FileLock;
 use crate::{auth::AuthResult, launcher};
+use java::JavaInstallation;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tracing::{info, warn};
 
 pub struct Launcher {
     pub minecraft_dir: MinecraftDir,
     pub file_manager: FileManager,
-    pub java_manager: JavaManager,
+    pub java_manager: Arc<Mutex<JavaManager>>,
     pub instance_manager: Arc<Mutex<InstanceManager>>,
 }
 
@@ -30,7 +53,8 @@ impl Launcher {
         let mut java_manager = JavaManager::new();
 
         // Initialize Java manager
-        java_manager.initialize();
+        java_manager.initialize(&minecraft_dir).await;
+        let java_manager = Arc::new(Mutex::new(java_manager));
 
         // Initialize instance manager with Arc<Mutex<>> for shared mutable access
         let instance_manager = Arc::new(Mutex::new(
@@ -67,6 +91,14 @@ impl Launcher {
             .download_assets(&version_info, &self.minecraft_dir)
             .await?;
 
+        // Download the log4j config the game's logging is set up with
+        self.file_manager
+            .download_log_config(&version_info, &self.minecraft_dir)
+            .await?;
+
+        // Download the Java runtime this version asks for, if it is missing
+        self.ensure_java_runtime(&version_info).await;
+
         Ok(())
     }
 
@@ -75,15 +107,90 @@ impl Launcher {
         version_id: &str,
         auth: &AuthResult,
         instance: Option<&InstanceConfig>,
-    ) -> Result<()> {
+        instance_lock: Option<&FileLock>,
+        options: &LaunchOptions,
+    ) -> Result<LaunchOutcome> {
         let version_info = self.file_manager.get_version_info(version_id).await?;
+
+        let java_path = instance.and_then(|inst| inst.settings.java_path.as_deref());
+        let java_version = instance.and_then(|inst| inst.settings.java_version);
+        // Dry runs download nothing and use whichever Java is already installed
+        if java_path.is_none() && java_version.is_none() && options.mode != LaunchMode::DryRun {
+            self.ensure_java_runtime(&version_info).await;
+        }
+
+        // Get the appropriate Java installation, honouring any instance override
+        let requirement = JavaManager::required_java(&version_info);
+        info!(
+            "Minecraft {} needs {requirement} ({})",
+            version_info.id, requirement.source
+        );
+        let java_installation =
+            self.java_manager
+                .lock()
+                .await
+                .resolve_java(&requirement, java_path, java_version)?;
+
         launcher::game::GameLauncher::launch(
             &version_info,
             auth,
             &self.minecraft_dir,
-            &self.java_manager,
+            &java_installation,
             instance,
+            instance_lock,
+            options,
         )
+        .await
+    }
+
+    /// Download and register a managed Java runtime
+    pub async fn install_java_runtime(&self, component: &str) -> Result<JavaInstallation> {
+        let java_path = self
+            .file_manager
+            .install_java_runtime(component, &self.minecraft_dir)
+            .await?;
+        let installation = JavaManager::probe_java_installation(&java_path)
+            .with_context(|| format!("Installed Java runtime {component} does not run"))?;
+
+        self.java_manager
+            .lock()
+            .await
+            .register(installation.clone());
+        Ok(installation)
+    }
+
+    /// Make sure the Java major version a Minecraft version asks for is available
+    ///
+    /// Failures only warn, so launching can still fall back to another installation.
+    async fn ensure_java_runtime(&self, version_info: &VersionInfo) {
+        let Some(required) = &version_info.java_version else {
+            return;
+        };
+        if self
+            .java_manager
+            .lock()
+            .await
+            .has_major(required.major_version)
+        {
+            return;
+        }
+
+        info!(
+            "Java {} is required for Minecraft {} but not installed, downloading {}",
+            required.major_version, version_info.id, required.component
+        );
+        match self.install_java_runtime(&required.component).await {
+            Ok(installation) => info!(
+                "✓ Java {} ready at {}",
+                installation.major_version,
+                installation.path.display()
+            ),
+            Err(e) => warn!(
+                "Could not install Java runtime {}: {}",
+                required.component,
+                e.to_string().lines().next().unwrap_or_default()
+            ),
+        }
     }
 }
 
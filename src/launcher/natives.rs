@@ -0,0 +1,420 @@
+//! Extraction of native libraries for a launch
+//!
+//! Natives are extracted into `versions/<id>/natives/<hash>`, keyed by the
+//! contents of the native jars and their exclude rules. When the libraries
+//! change they land in a fresh directory instead of mixing with stale files,
+//! and while nothing changes the directory is reused, which keeps it valid for
+//! exported scripts and games started in the background. After a launch the
+//! directories of earlier library sets are removed, except those a running
+//! game still holds the `<hash>.lock` of.
+
+use crate::error::{FileManagerError, GameError, Result, ResultExt};
+use crate::fs_utils::{self, FileLock};
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use zip::ZipArchive;
+
+use crate::launcher::files::FileManager;
+use crate::launcher::minecraft_dir::MinecraftDir;
+use crate::launcher::version::{Library, VersionInfo};
+
+/// Hex digits of the content hash used in directory names
+const HASH_LENGTH: usize = 16;
+
+/// Age after which an unfinished extraction is considered abandoned
+const PARTIAL_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Extracted natives of a launch, kept from removal while this is alive
+#[derive(Debug)]
+pub struct Natives {
+    pub dir: PathBuf,
+    lock: FileLock,
+}
+
+impl Natives {
+    /// Shared lock on the directory, to be handed to a game started in the background
+    pub fn lock(&self) -> &FileLock {
+        &self.lock
+    }
+}
+
+/// Extract the natives of a version and lock the directory holding them
+///
+/// # Errors
+///
+/// Returns an error if a native jar is missing or unreadable, or contains an
+/// entry that would be written outside the natives directory.
+pub fn prepare(version_info: &VersionInfo, minecraft_dir: &MinecraftDir) -> Result<Natives> {
+    let jars = native_jars(version_info, minecraft_dir)?;
+    let root = minecraft_dir.natives_dir(&version_info.id);
+    std::fs::create_dir_all(&root)
+        .with_context(|| format!("Failed to create natives directory: {}", root.display()))?;
+    let natives_dir = root.join(content_hash(&jars)?);
+
+    // Lock before looking at the directory, so it cannot be removed as stale in between
+    let lock = FileLock::shared(&fs_utils::sibling_path(&natives_dir, ".lock"))?;
+    let natives = Natives {
+        dir: natives_dir,
+        lock,
+    };
+    let natives_dir = &natives.dir;
+
+    if natives_dir.is_dir() {
+        debug!("Reusing natives in {}", natives_dir.display());
+        return Ok(natives);
+    }
+
+    // Extract next to the final directory and move it into place once complete,
+    // so an interrupted extraction is never mistaken for a finished one
+    let partial = root.join(format!(".partial-{}", uuid::Uuid::new_v4()));
+    let extracted = jars
+        .iter()
+        .try_for_each(|(jar, library)| extract(jar, &partial, library))
+        .and_then(|()| {
+            std::fs::create_dir_all(&partial).with_context(|| {
+                format!("Failed to create natives directory: {}", partial.display())
+            })
+        });
+    if let Err(e) = extracted {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+
+    if let Err(e) = std::fs::rename(&partial, natives_dir) {
+        let _ = std::fs::remove_dir_all(&partial);
+        // Another launch may have extracted the same natives in the meantime
+        if !natives_dir.is_dir() {
+            return Err(FileManagerError::extraction_failed(format!(
+                "Failed to move natives into {}: {e}",
+                natives_dir.display()
+            ))
+            .into());
+        }
+    }
+
+    info!(
+        "Extracted natives of {} libraries to {}",
+        jars.len(),
+        natives_dir.display()
+    );
+    Ok(natives)
+}
+
+/// Remove the natives directories of the version that no launch uses anymore
+///
+/// Directories whose lock is held by a running game are kept, as are
+/// extractions still in progress. Lock files are left in place: removing one
+/// could let a launch waiting on it lock a file nobody else sees.
+pub fn remove_stale(current: &Path) {
+    let Some(root) = current.parent() else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path == current || name.ends_with(".lock") {
+            continue;
+        }
+
+        let removed = if name.starts_with(".partial-") {
+            if !is_abandoned(&path) {
+                continue;
+            }
+            std::fs::remove_dir_all(&path)
+        } else if path.is_dir() {
+            match FileLock::try_exclusive(&fs_utils::sibling_path(&path, ".lock")) {
+                Ok(Some(_lock)) => std::fs::remove_dir_all(&path),
+                Ok(None) => {
+                    debug!(
+                        "Keeping natives {}, a running game uses them",
+                        path.display()
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Keeping natives {}: {e}", path.display());
+                    continue;
+                }
+            }
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match removed {
+            Ok(()) => debug!("Removed stale natives {}", path.display()),
+            Err(e) => warn!("Failed to remove stale natives {}: {e}", path.display()),
+        }
+    }
+}
+
+/// Whether an unfinished extraction has not been touched for a long time
+fn is_abandoned(partial: &Path) -> bool {
+    std::fs::metadata(partial)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > PARTIAL_MAX_AGE)
+}
+
+/// Native jars of this platform and the libraries they belong to
+fn native_jars<'a>(
+    version_info: &'a VersionInfo,
+    minecraft_dir: &MinecraftDir,
+) -> Result<Vec<(PathBuf, &'a Library)>> {
+    let selected: Vec<String> = FileManager::filter_native_libraries_by_architecture(
+        &version_info.libraries,
+        std::env::consts::ARCH,
+    )
+    .into_iter()
+    .map(|library| library.name)
+    .collect();
+
+    let mut jars = Vec::new();
+    for library in &version_info.libraries {
+        if !library.should_use() {
+            continue;
+        }
+
+        let coordinate = if library.is_native_library() {
+            if !selected.contains(&library.name) || library.downloads.artifact.is_none() {
+                continue;
+            }
+            library.coordinate()?
+        } else if let Some(classifier) = library.get_native_classifier()
+            && library
+                .downloads
+                .classifiers
+                .as_ref()
+                .is_some_and(|classifiers| classifiers.contains_key(&classifier))
+        {
+            library.coordinate()?.with_classifier(&classifier)
+        } else {
+            continue;
+        };
+
+        let jar = minecraft_dir.library_path(&coordinate.path());
+        if !jar.exists() {
+            return Err(GameError::launch_failed(format!(
+                "Native library {coordinate} is missing at {}, run 'rustified prepare {}' to download it",
+                jar.display(),
+                version_info.id
+            ))
+            .into());
+        }
+        jars.push((jar, library));
+    }
+    Ok(jars)
+}
+
+/// Hash of the native jars' contents and exclude rules
+fn content_hash(jars: &[(PathBuf, &Library)]) -> Result<String> {
+    let mut hasher = Sha1::new();
+    for (jar, library) in jars {
+        let content = std::fs::read(jar)
+            .with_context(|| format!("Failed to read native library: {}", jar.display()))?;
+        hasher.update(&content);
+        for pattern in excluded_prefixes(library) {
+            hasher.update(pattern.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update([0xff]);
+    }
+    let hash = format!("{:x}", hasher.finalize());
+    Ok(hash[..HASH_LENGTH].to_string())
+}
+
+/// Extract the files of one native jar into `natives_dir`
+fn extract(jar: &Path, natives_dir: &Path, library: &Library) -> Result<()> {
+    debug!(
+        "Extracting natives from {} to {}",
+        jar.display(),
+        natives_dir.display()
+    );
+    let file =
+        File::open(jar).with_context(|| format!("Failed to open JAR file: {}", jar.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read JAR as ZIP archive: {}", jar.display()))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .with_context(|| format!("Failed to read file at index {i} from JAR"))?;
+        let name = entry.name().to_string();
+
+        if entry.is_dir() || name.starts_with("META-INF/") || is_excluded(&name, library) {
+            continue;
+        }
+        let Some(relative) = entry_path(&name) else {
+            return Err(FileManagerError::extraction_failed(format!(
+                "Refusing to extract '{name}' from {}, it points outside the natives directory",
+                jar.display()
+            ))
+            .into());
+        };
+
+        let output_path = natives_dir.join(relative);
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let mut output = File::create(&output_path)
+            .with_context(|| format!("Failed to create file: {}", output_path.display()))?;
+        std::io::copy(&mut entry, &mut output)
+            .with_context(|| format!("Failed to extract file: {name}"))?;
+    }
+    Ok(())
+}
+
+/// Path of an archive entry relative to the natives directory
+///
+/// Absolute paths, drive prefixes and `..` components are rejected, with
+/// both `/` and `\` treated as separators.
+fn entry_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) || name.contains(':') {
+        return None;
+    }
+
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => {
+                // Guard against anything the platform would still interpret specially
+                if !matches!(
+                    Path::new(part).components().next(),
+                    Some(Component::Normal(_))
+                ) {
+                    return None;
+                }
+                path.push(part);
+            }
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// The library's `extract.exclude` entries, which are path prefixes
+fn excluded_prefixes(library: &Library) -> &[String] {
+    library
+        .extract
+        .as_ref()
+        .and_then(|extract| extract.exclude.as_deref())
+        .unwrap_or_default()
+}
+
+/// Whether an archive entry is excluded by the library's extract rules
+fn is_excluded(name: &str, library: &Library) -> bool {
+    excluded_prefixes(library)
+        .iter()
+        .any(|prefix| name.starts_with(prefix.as_str()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+    use std::io::Write;
+
+    #[test]
+    fn test_entry_paths_stay_inside() {
+        assert_eq!(
+            entry_path("linux/x64/org/lwjgl/liblwjgl.so"),
+            Some(PathBuf::from("linux/x64/org/lwjgl/liblwjgl.so"))
+        );
+        assert_eq!(entry_path("./lwjgl.dll"), Some(PathBuf::from("lwjgl.dll")));
+        for name in [
+            "../evil.so",
+            "natives/../../evil.so",
+            "..\\evil.dll",
+            "/etc/passwd",
+            "\\evil.dll",
+            "C:\\evil.dll",
+            "",
+        ] {
+            assert_eq!(entry_path(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_extract_rejects_zip_slip_and_excludes_by_prefix() {
+        let dir = test_support::temp_dir("natives");
+        std::fs::create_dir_all(&dir).unwrap();
+        let jar = dir.join("natives.jar");
+        let write_jar = |entries: &[&str]| {
+            let mut zip = zip::ZipWriter::new(File::create(&jar).unwrap());
+            for entry in entries {
+                zip.start_file(*entry, zip::write::FileOptions::default())
+                    .unwrap();
+                zip.write_all(b"native").unwrap();
+            }
+            zip.finish().unwrap();
+        };
+        let library: Library = serde_json::from_value(serde_json::json!({
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "extract": { "exclude": ["META-INF/", "docs/"] }
+        }))
+        .unwrap();
+
+        write_jar(&[
+            "liblwjgl.so",
+            "docs/readme.txt",
+            "natives/docs/readme.txt",
+            "META-INF/MANIFEST.MF",
+        ]);
+        let out = dir.join("out");
+        extract(&jar, &out, &library).unwrap();
+        assert!(out.join("liblwjgl.so").exists());
+        assert!(!out.join("docs").exists());
+        // Exclusions are prefixes, not substrings
+        assert!(out.join("natives/docs/readme.txt").exists());
+
+        write_jar(&["liblwjgl.so", "../escaped.so"]);
+        assert!(extract(&jar, &dir.join("slip"), &library).is_err());
+        assert!(!dir.join("escaped.so").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_stale_keeps_natives_in_use() {
+        let root = test_support::temp_dir("natives-stale");
+        let dir = |name: &str| {
+            let path = root.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            path
+        };
+        let current = dir("current");
+        let stale = dir("stale");
+        let running = dir("running");
+        let extracting = dir(".partial-new");
+        let abandoned = dir(".partial-old");
+        File::open(&abandoned)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let running_lock = FileLock::shared(&fs_utils::sibling_path(&running, ".lock")).unwrap();
+
+        remove_stale(&current);
+        assert!(current.is_dir());
+        assert!(running.is_dir());
+        assert!(extracting.is_dir());
+        assert!(!stale.exists());
+        assert!(!abandoned.exists());
+
+        // Once the game exits its natives go too
+        drop(running_lock);
+        remove_stale(&current);
+        assert!(!running.exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
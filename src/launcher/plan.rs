@@ -0,0 +1,232 @@
+//! Fully resolved launch commands
+//!
+//! A [`LaunchPlan`] is what would be executed to start the game: the Java
+//! executable, its arguments, the working directory and environment changes.
+//! It can be shown with secrets such as the access token redacted, or
+//! exported as a shell script that starts the game without the launcher.
+
+use crate::error::{Result, ResultExt};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Placeholder shown instead of a secret
+const REDACTED: &str = "<redacted>";
+
+/// Everything needed to start the game process
+#[derive(Debug, Clone)]
+pub struct LaunchPlan {
+    /// Java executable, or the instance's wrapper command
+    pub program: PathBuf,
+    /// Arguments passed to the program, unredacted
+    pub args: Vec<String>,
+    /// Directory the game runs in
+    pub working_dir: Option<PathBuf>,
+    /// Environment variables set (`Some`) or removed (`None`) for the game
+    pub env: Vec<(String, Option<String>)>,
+    /// Values that must not be shown, such as the access token
+    secrets: Vec<String>,
+}
+
+impl LaunchPlan {
+    /// Capture a command, remembering which values are secret
+    pub fn new(cmd: &Command, secrets: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            program: PathBuf::from(cmd.get_program()),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            working_dir: cmd.get_current_dir().map(Path::to_path_buf),
+            env: cmd
+                .get_envs()
+                .map(|(key, value)| {
+                    (
+                        key.to_string_lossy().into_owned(),
+                        value.map(|value| value.to_string_lossy().into_owned()),
+                    )
+                })
+                .collect(),
+            secrets: secrets
+                .into_iter()
+                .filter(|secret| !secret.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Replace every secret in `text` with a placeholder
+    pub fn redact(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+    }
+
+    /// Arguments with secrets redacted
+    pub fn redacted_args(&self) -> Vec<String> {
+        self.args.iter().map(|arg| self.redact(arg)).collect()
+    }
+
+    /// The whole command on one line, shell-quoted and with secrets redacted
+    pub fn command_line(&self) -> String {
+        std::iter::once(shell_quote(&self.program.to_string_lossy()))
+            .chain(self.redacted_args().iter().map(|arg| shell_quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A POSIX shell script that reproduces the launch
+    ///
+    /// The script contains the access token, since the game cannot start without it.
+    pub fn shell_script(&self) -> String {
+        let mut lines = vec![
+            "#!/bin/sh".to_string(),
+            "# Generated by rustified. Contains your access token, do not share it.".to_string(),
+            "# The token expires, export the script again if the game rejects it.".to_string(),
+        ];
+        if let Some(dir) = &self.working_dir {
+            lines.push(format!(
+                "cd {} || exit 1",
+                shell_quote(&dir.to_string_lossy())
+            ));
+        }
+        for (key, value) in &self.env {
+            lines.push(match value {
+                Some(value) => format!("export {key}={}", shell_quote(value)),
+                None => format!("unset {key}"),
+            });
+        }
+
+        let command = std::iter::once(format!(
+            "exec {}",
+            shell_quote(&self.program.to_string_lossy())
+        ))
+        .chain(
+            self.args
+                .iter()
+                .map(|arg| format!("  {}", shell_quote(arg))),
+        )
+        .collect::<Vec<_>>()
+        .join(" \\\n");
+        lines.push(command);
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Write [`Self::shell_script`] to `path`, readable and executable only by the owner
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write_script(&self, path: &Path) -> Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // The script holds the access token, so it must never be readable by others
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o700);
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to create launch script: {}", path.display()))?;
+
+        // An existing file keeps its mode when opened, so restrict it before writing
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o700))
+                .with_context(|| {
+                    format!(
+                        "Failed to restrict launch script permissions: {}",
+                        path.display()
+                    )
+                })?;
+        }
+
+        file.write_all(self.shell_script().as_bytes())
+            .with_context(|| format!("Failed to write launch script: {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Quote a word for a POSIX shell, leaving plain words untouched
+fn shell_quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | '=' | ',' | '+' | '@')
+        });
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn plan() -> LaunchPlan {
+        let mut cmd = Command::new("/usr/bin/java");
+        cmd.args([
+            "-cp",
+            "a.jar:b.jar",
+            "--accessToken",
+            "secret-token",
+            "--session",
+            "token:secret-token:uuid",
+            "--username",
+            "Steve's alt",
+        ]);
+        cmd.current_dir("/games/my world");
+        cmd.env("MESA_GL_VERSION_OVERRIDE", "4.5");
+        cmd.env_remove("_JAVA_OPTIONS");
+        LaunchPlan::new(&cmd, ["secret-token".to_string(), String::new()])
+    }
+
+    #[test]
+    fn test_command_line_redacts_secrets() {
+        let line = plan().command_line();
+        assert!(!line.contains("secret-token"));
+        assert_eq!(
+            line,
+            "/usr/bin/java -cp a.jar:b.jar --accessToken '<redacted>' --session 'token:<redacted>:uuid' --username 'Steve'\\''s alt'"
+        );
+    }
+
+    #[test]
+    fn test_shell_script_reproduces_launch() {
+        let script = plan().shell_script();
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("cd '/games/my world' || exit 1\n"));
+        assert!(script.contains("export MESA_GL_VERSION_OVERRIDE=4.5\n"));
+        assert!(script.contains("unset _JAVA_OPTIONS\n"));
+        assert!(script.contains("  --accessToken \\\n  secret-token \\\n"));
+        assert!(script.ends_with("'Steve'\\''s alt'\n"));
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_write_script_is_private_from_the_start() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = crate::launcher::test_support::temp_dir("plan-script");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("launch.sh");
+
+        plan().write_script(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        // An existing world-readable file is restricted too
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        plan().write_script(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            plan().shell_script()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,388 @@
+//! Supervision of the game process
+//!
+//! Attached launches pipe the game's stdout and stderr through the launcher,
+//! which echoes them to the terminal (parsed, filtered and formatted like the
+//! launcher's own log lines) and copies the raw output into a per-launch log
+//! file. Ctrl-C asks the game to shut down instead of killing it outright.
+//! Detached launches start the game in its own session, write its output
+//! straight to the log file and return immediately.
+
+use crate::error::{Result, ResultExt};
+use crate::fs_utils::FileLock;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::launcher::game_log::{GameLogLevel, GameLogLine, GameLogParser};
+use tracing::{debug, info, warn};
+
+/// File name prefix of the logs written by the launcher
+const LAUNCHER_LOG_PREFIX: &str = "launcher-";
+
+/// Number of launcher logs kept per game directory
+const MAX_LAUNCHER_LOGS: usize = 10;
+
+/// How the exit of an attached game process came about
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessExit {
+    /// Exit code (`None` if killed by a signal)
+    pub code: Option<i32>,
+    /// Signal that terminated the process, on unix
+    pub signal: Option<i32>,
+    /// Whether the launcher stopped the game because of Ctrl-C
+    pub stopped_by_user: bool,
+}
+
+/// Create the logs directory, drop old launcher logs and return the path for a new one
+pub fn new_log_path(logs_dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(logs_dir)
+        .with_context(|| format!("Failed to create logs directory: {}", logs_dir.display()))?;
+    rotate_logs(logs_dir, MAX_LAUNCHER_LOGS - 1);
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    Ok(logs_dir.join(format!("{LAUNCHER_LOG_PREFIX}{timestamp}.log")))
+}
+
+/// Delete the oldest launcher logs so at most `keep` remain
+fn rotate_logs(logs_dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(logs_dir) else {
+        return;
+    };
+    let mut logs: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "log")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(LAUNCHER_LOG_PREFIX))
+        })
+        .collect();
+    if logs.len() <= keep {
+        return;
+    }
+
+    // Timestamps in the names sort chronologically
+    logs.sort();
+    for old_log in &logs[..logs.len() - keep] {
+        debug!("Removing old launcher log: {}", old_log.display());
+        if let Err(e) = std::fs::remove_file(old_log) {
+            warn!("Failed to remove old log {}: {e}", old_log.display());
+        }
+    }
+}
+
+/// Start the game in its own session with output going to `log_path`, returning its PID
+///
+/// On unix the game inherits `locks` and holds them until it exits. Elsewhere
+/// they are released when the launcher exits.
+pub fn spawn_detached(mut cmd: Command, log_path: &Path, locks: &[&FileLock]) -> Result<u32> {
+    let log_file = File::create(log_path)
+        .with_context(|| format!("Failed to create log file: {}", log_path.display()))?;
+    let stderr_file = log_file
+        .try_clone()
+        .context("Failed to duplicate log file handle")?;
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::from(log_file))
+        .stderr(Stdio::from(stderr_file));
+    detach_from_terminal(&mut cmd);
+    #[cfg(unix)]
+    for lock in locks {
+        lock.inherit(&mut cmd);
+    }
+    #[cfg(not(unix))]
+    if !locks.is_empty() {
+        warn!("The instance is not kept locked while the game runs in the background");
+    }
+
+    let child = cmd.spawn().context("Failed to start Minecraft process")?;
+    Ok(child.id())
+}
+
+#[cfg(unix)]
+fn detach_from_terminal(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: setsid is async-signal-safe and touches no memory shared with the parent
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(windows)]
+fn detach_from_terminal(cmd: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+/// Run the game attached to the launcher, teeing its output into `log_path`
+///
+/// Game log events below `echo_level` are kept out of the terminal but still logged.
+pub async fn run_attached(
+    mut cmd: Command,
+    log_path: &Path,
+    echo_level: GameLogLevel,
+) -> Result<ProcessExit> {
+    let log_file = tokio::fs::File::create(log_path)
+        .await
+        .with_context(|| format!("Failed to create log file: {}", log_path.display()))?;
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Keep the terminal's Ctrl-C away from the game so the launcher can stop it gracefully
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+    let mut child = tokio::process::Command::from(cmd)
+        .spawn()
+        .context("Failed to start Minecraft process")?;
+    let pid = child.id();
+    if let Some(pid) = pid {
+        info!("Minecraft process started with PID: {pid}");
+    }
+    info!("Logging game output to {}", log_path.display());
+
+    let (log_tx, log_rx) = mpsc::unbounded_channel();
+    let writer = tokio::spawn(write_log(log_file, log_rx));
+    let mut tees = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        tees.push(tokio::spawn(tee_lines(
+            stdout,
+            false,
+            echo_level,
+            log_tx.clone(),
+        )));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tees.push(tokio::spawn(tee_lines(
+            stderr,
+            true,
+            echo_level,
+            log_tx.clone(),
+        )));
+    }
+    drop(log_tx);
+
+    let mut stopped_by_user = false;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status.context("Failed to wait for Minecraft process")?,
+            _ = tokio::signal::ctrl_c() => {
+                if stopped_by_user {
+                    warn!("Killing Minecraft");
+                    let _ = child.start_kill();
+                } else {
+                    info!("Stopping Minecraft... (press Ctrl-C again to force)");
+                    stopped_by_user = true;
+                    request_stop(&mut child, pid);
+                }
+            }
+        }
+    };
+
+    for tee in tees {
+        let _ = tee.await;
+    }
+    match writer.await {
+        Ok(Err(e)) => warn!("Failed to write game log: {e}"),
+        Err(e) => warn!("Game log writer failed: {e}"),
+        Ok(Ok(())) => {}
+    }
+
+    Ok(ProcessExit {
+        code: status.code(),
+        signal: exit_signal(status),
+        stopped_by_user,
+    })
+}
+
+/// Ask the game to shut down, running its shutdown hooks
+#[cfg(unix)]
+fn request_stop(_child: &mut tokio::process::Child, pid: Option<u32>) {
+    if let Some(pid) = pid.and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+        // SAFETY: kill has no memory-safety preconditions
+        unsafe {
+            libc::kill(pid, libc::SIGTERM);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn request_stop(child: &mut tokio::process::Child, _pid: Option<u32>) {
+    // The console already delivered Ctrl-C to the game; make sure it goes away
+    let _ = child.start_kill();
+}
+
+#[cfg(unix)]
+fn exit_signal(status: std::process::ExitStatus) -> Option<i32> {
+    std::os::unix::process::ExitStatusExt::signal(&status)
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Echo a stream line by line to the terminal and forward each line to the log writer
+async fn tee_lines(
+    stream: impl AsyncRead + Unpin,
+    is_stderr: bool,
+    echo_level: GameLogLevel,
+    log_tx: mpsc::UnboundedSender<Vec<u8>>,
+) {
+    let mut reader = BufReader::new(stream);
+    let mut parser = GameLogParser::default();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if let Some(parsed) = parser.push_line(&String::from_utf8_lossy(&line)) {
+                    echo(&parsed, is_stderr, echo_level);
+                }
+                let _ = log_tx.send(line.clone());
+            }
+        }
+    }
+    if let Some(parsed) = parser.finish() {
+        echo(&parsed, is_stderr, echo_level);
+    }
+}
+
+/// Print a parsed line of game output unless it is below `echo_level`
+fn echo(line: &GameLogLine, is_stderr: bool, echo_level: GameLogLevel) {
+    let Some(text) = echo_text(line, echo_level) else {
+        return;
+    };
+
+    // A closed terminal must not stop the game log from being written
+    let _ = if is_stderr {
+        writeln!(std::io::stderr(), "{text}")
+    } else {
+        writeln!(std::io::stdout(), "{text}")
+    };
+}
+
+/// Terminal text for a parsed line, `None` for events below `echo_level`
+///
+/// Raw output has no level and is always shown.
+fn echo_text(line: &GameLogLine, echo_level: GameLogLevel) -> Option<String> {
+    match line {
+        GameLogLine::Event(event) if event.level < echo_level => None,
+        GameLogLine::Event(event) => Some(event.format()),
+        GameLogLine::Raw(raw) => Some(raw.trim_end().to_string()),
+    }
+}
+
+/// Write every received line to the log file
+async fn write_log(
+    file: tokio::fs::File,
+    mut log_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+) -> std::io::Result<()> {
+    let mut file = tokio::io::BufWriter::new(file);
+    while let Some(line) = log_rx.recv().await {
+        file.write_all(&line).await?;
+    }
+    file.flush().await
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::test_support;
+
+    #[test]
+    fn test_rotate_logs_keeps_newest() {
+        let dir = test_support::temp_dir("logs");
+        std::fs::create_dir_all(&dir).unwrap();
+        for day in 1..=5 {
+            std::fs::write(
+                dir.join(format!("launcher-2024-01-0{day}_00-00-00.log")),
+                "",
+            )
+            .unwrap();
+        }
+        std::fs::write(dir.join("latest.log"), "").unwrap();
+
+        rotate_logs(&dir, 2);
+
+        let mut remaining: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                "latest.log",
+                "launcher-2024-01-04_00-00-00.log",
+                "launcher-2024-01-05_00-00-00.log",
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_echo_filters_events_by_level() {
+        let mut parser = crate::launcher::game_log::GameLogParser::default();
+        let debug = parser
+            .push_line("[12:34:56] [main/DEBUG]: Loading")
+            .unwrap();
+        let warn = parser
+            .push_line("[12:34:56] [main/WARN]: Slow tick")
+            .unwrap();
+        let raw = GameLogLine::Raw("\tat Foo.bar(Foo.java:1)\n".to_string());
+
+        assert!(echo_text(&debug, GameLogLevel::Info).is_none());
+        assert!(echo_text(&debug, GameLogLevel::Debug).is_some());
+        assert!(
+            echo_text(&warn, GameLogLevel::Warn)
+                .unwrap()
+                .contains("Slow tick")
+        );
+        assert!(echo_text(&warn, GameLogLevel::Error).is_none());
+        assert_eq!(
+            echo_text(&raw, GameLogLevel::Fatal).as_deref(),
+            Some("\tat Foo.bar(Foo.java:1)")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_attached_tees_output_and_records_exit() {
+        let dir = test_support::temp_dir("logs");
+        std::fs::create_dir_all(&dir).unwrap();
+        let log_path = dir.join("launcher-test.log");
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let exit = run_attached(cmd, &log_path, GameLogLevel::Info)
+            .await
+            .unwrap();
+
+        assert_eq!(exit.code, Some(3));
+        assert!(!exit.stopped_by_user);
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains("out\n"));
+        assert!(log.contains("err\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+//! Evaluation of the `rules` found on libraries and arguments in version JSONs
+//!
+//! Rules are checked in order, starting from "disallow"; every rule that
+//! matches the current platform and launch features sets the outcome to its
+//! action, so the last matching rule wins.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use tracing::warn;
+
+use crate::launcher::version::{OsRule, Rule};
+
+/// Launcher features that feature-gated rules are checked against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LaunchFeatures {
+    /// The account does not own the game
+    pub is_demo_user: bool,
+    /// A window size was configured
+    pub has_custom_resolution: bool,
+    /// Kind of Quick Play target requested, if any
+    pub quick_play: Option<QuickPlayKind>,
+}
+
+/// Where Quick Play takes the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickPlayKind {
+    Singleplayer,
+    Multiplayer,
+    Realms,
+}
+
+impl LaunchFeatures {
+    /// Whether a named feature is enabled; features the launcher does not know are off
+    pub fn is_enabled(self, name: &str) -> bool {
+        match name {
+            "is_demo_user" => self.is_demo_user,
+            "has_custom_resolution" => self.has_custom_resolution,
+            "has_quick_plays_support" => self.quick_play.is_some(),
+            "is_quick_play_singleplayer" => self.quick_play == Some(QuickPlayKind::Singleplayer),
+            "is_quick_play_multiplayer" => self.quick_play == Some(QuickPlayKind::Multiplayer),
+            "is_quick_play_realms" => self.quick_play == Some(QuickPlayKind::Realms),
+            _ => false,
+        }
+    }
+}
+
+/// Whether a list of rules allows something on this platform with these features
+///
+/// An empty list allows nothing, callers treat a missing `rules` field as "allow".
+pub fn rules_allow(rules: &[Rule], features: LaunchFeatures) -> bool {
+    rules.iter().fold(false, |allowed, rule| {
+        if rule_matches(rule, features) {
+            rule.action == "allow"
+        } else {
+            allowed
+        }
+    })
+}
+
+/// Whether a single rule applies
+fn rule_matches(rule: &Rule, features: LaunchFeatures) -> bool {
+    let os_matches = rule.os.as_ref().is_none_or(os_rule_matches);
+    let features_match = rule.features.as_ref().is_none_or(|required| {
+        required
+            .iter()
+            .all(|(name, value)| features.is_enabled(name) == *value)
+    });
+    os_matches && features_match
+}
+
+fn os_rule_matches(os_rule: &OsRule) -> bool {
+    if let Some(name) = &os_rule.name
+        && Some(name.as_str()) != current_os_name()
+    {
+        return false;
+    }
+
+    if let Some(arch) = &os_rule.arch
+        && !arch_matches(arch, std::env::consts::ARCH)
+    {
+        return false;
+    }
+
+    os_rule
+        .version
+        .as_ref()
+        .is_none_or(|pattern| os_version_matches(pattern))
+}
+
+/// Whether the OS version matches an `os.version` pattern
+///
+/// The OS version never changes while the launcher runs, so each pattern is
+/// compiled and matched once and the outcome reused.
+fn os_version_matches(pattern: &str) -> bool {
+    static OUTCOMES: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+    let mut outcomes = OUTCOMES
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    *outcomes
+        .entry(pattern.to_string())
+        .or_insert_with(|| match Regex::new(pattern) {
+            Ok(regex) => current_os_version().is_some_and(|version| regex.is_match(version)),
+            Err(e) => {
+                warn!("Ignoring rule with invalid os.version pattern '{pattern}': {e}");
+                false
+            }
+        })
+}
+
+/// The current OS under the name version JSONs use
+pub fn current_os_name() -> Option<&'static str> {
+    match std::env::consts::OS {
+        "windows" => Some("windows"),
+        "linux" => Some("linux"),
+        "macos" => Some("osx"),
+        _ => None,
+    }
+}
+
+/// Whether a rule's `arch` names a Rust target architecture
+///
+/// Version JSONs use `x86` for 32-bit x86, `arm64`/`aarch64` for 64-bit ARM
+/// and `arm32` for 32-bit ARM.
+pub fn arch_matches(rule_arch: &str, arch: &str) -> bool {
+    match rule_arch {
+        "x86" | "i386" | "i686" => arch == "x86",
+        "x86_64" | "x64" | "amd64" => arch == "x86_64",
+        "arm64" | "aarch64" => arch == "aarch64",
+        "arm32" | "arm" => arch == "arm",
+        other => other == arch,
+    }
+}
+
+/// Split a native classifier like `natives-linux-arm64` into `natives-linux` and `arm64`
+///
+/// Classifiers without an architecture suffix are built for `x86_64`.
+pub fn split_native_arch(classifier: &str) -> (&str, Option<&str>) {
+    match classifier.rsplit_once('-') {
+        Some((base, arch)) if NATIVE_ARCH_SUFFIXES.contains(&arch) => (base, Some(arch)),
+        _ => (classifier, None),
+    }
+}
+
+/// Architecture suffixes found on native classifiers
+const NATIVE_ARCH_SUFFIXES: [&str; 7] =
+    ["x86", "x64", "x86_64", "arm64", "aarch64", "arm32", "arm"];
+
+/// Value of `${arch}` in legacy native classifiers such as `natives-windows-${arch}`
+pub fn current_arch_bits() -> &'static str {
+    if cfg!(target_pointer_width = "64") {
+        "64"
+    } else {
+        "32"
+    }
+}
+
+/// Version of the running OS as Java reports it in `os.version`
+fn current_os_version() -> Option<&'static str> {
+    static OS_VERSION: OnceLock<Option<String>> = OnceLock::new();
+    OS_VERSION.get_or_init(detect_os_version).as_deref()
+}
+
+#[cfg(target_os = "linux")]
+fn detect_os_version() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn detect_os_version() -> Option<String> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(windows)]
+fn detect_os_version() -> Option<String> {
+    // "Microsoft Windows [Version 10.0.19045.3570]" -> "10.0", like Java's os.version
+    let output = std::process::Command::new("cmd")
+        .args(["/C", "ver"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let version = text
+        .split("Version ")
+        .nth(1)?
+        .trim_end_matches([']', '\r', '\n']);
+    let mut parts = version.split('.');
+    Some(format!("{}.{}", parts.next()?, parts.next()?))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn detect_os_version() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn rules(json: serde_json::Value) -> Vec<Rule> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_last_matching_rule_wins() {
+        let os = current_os_name().unwrap();
+        let allow_except_here = rules(serde_json::json!([
+            { "action": "allow" },
+            { "action": "disallow", "os": { "name": os } }
+        ]));
+        assert!(!rules_allow(&allow_except_here, LaunchFeatures::default()));
+
+        let only_elsewhere = rules(serde_json::json!([
+            { "action": "allow", "os": { "name": "not-an-os" } }
+        ]));
+        assert!(!rules_allow(&only_elsewhere, LaunchFeatures::default()));
+
+        let this_arch = rules(serde_json::json!([
+            { "action": "allow", "os": { "arch": std::env::consts::ARCH } }
+        ]));
+        assert!(rules_allow(&this_arch, LaunchFeatures::default()));
+        assert!(!rules_allow(&[], LaunchFeatures::default()));
+
+        // Invalid version patterns never match, however often they are evaluated
+        let bad_pattern = rules(serde_json::json!([
+            { "action": "allow", "os": { "name": os, "version": "^10\\.(" } }
+        ]));
+        for _ in 0..2 {
+            assert!(!rules_allow(&bad_pattern, LaunchFeatures::default()));
+        }
+    }
+
+    #[test]
+    fn test_feature_rules() {
+        let demo = rules(serde_json::json!([
+            { "action": "allow", "features": { "is_demo_user": true } }
+        ]));
+        assert!(!rules_allow(&demo, LaunchFeatures::default()));
+        assert!(rules_allow(
+            &demo,
+            LaunchFeatures {
+                is_demo_user: true,
+                ..LaunchFeatures::default()
+            }
+        ));
+
+        let unknown = rules(serde_json::json!([
+            { "action": "allow", "features": { "has_future_feature": true } }
+        ]));
+        assert!(!rules_allow(&unknown, LaunchFeatures::default()));
+    }
+
+    #[test]
+    fn test_arch_names() {
+        assert!(arch_matches("x86", "x86"));
+        assert!(!arch_matches("x86", "x86_64"));
+        assert!(arch_matches("arm64", "aarch64"));
+        assert!(arch_matches("x86_64", "x86_64"));
+        assert!(arch_matches("arm32", "arm"));
+        assert!(!arch_matches("arm32", "aarch64"));
+
+        assert_eq!(
+            split_native_arch("natives-linux-arm64"),
+            ("natives-linux", Some("arm64"))
+        );
+        assert_eq!(
+            split_native_arch("natives-windows-x86"),
+            ("natives-windows", Some("x86"))
+        );
+        assert_eq!(split_native_arch("natives-linux"), ("natives-linux", None));
+    }
+}
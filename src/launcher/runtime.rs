@@ -0,0 +1,548 @@
+//! Managed Java runtimes downloaded from Mojang's java-runtime manifest
+//!
+//! The manifest lists, per platform, every runtime component (e.g.
+//! `java-runtime-gamma`) together with a file manifest describing each file,
+//! directory and symlink of that runtime. Runtimes are installed into
+//! `runtime/<component>/<platform>/<component>` like the official launcher does.
+
+use crate::error::{FileManagerError, JavaError, Result, ResultExt};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use tracing::{debug, info};
+
+use crate::launcher::files::FileManager;
+use crate::launcher::minecraft_dir::MinecraftDir;
+use crate::launcher::version::DownloadInfo;
+
+/// Number of runtime files downloaded concurrently
+const BATCH_SIZE: usize = 16;
+
+/// Runtime components available for each platform
+type RuntimeIndex = HashMap<String, HashMap<String, Vec<RuntimeVariant>>>;
+
+#[derive(Debug, Deserialize)]
+struct RuntimeVariant {
+    manifest: DownloadInfo,
+    version: RuntimeVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuntimeVersion {
+    name: String,
+}
+
+/// File manifest of a single runtime component
+#[derive(Debug, Deserialize)]
+pub struct RuntimeManifest {
+    pub files: BTreeMap<String, RuntimeFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RuntimeFile {
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: RuntimeFileDownloads,
+    },
+    Directory,
+    Link {
+        target: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RuntimeFileDownloads {
+    pub raw: DownloadInfo,
+}
+
+/// Platform key used by the runtime manifest for the current OS and architecture
+pub fn runtime_platform() -> Option<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Some("linux"),
+        ("linux", "x86") => Some("linux-i386"),
+        ("macos", "x86_64") => Some("mac-os"),
+        ("macos", "aarch64") => Some("mac-os-arm64"),
+        ("windows", "x86_64") => Some("windows-x64"),
+        ("windows", "x86") => Some("windows-x86"),
+        ("windows", "aarch64") => Some("windows-arm64"),
+        _ => None,
+    }
+}
+
+/// Path of the java executable inside an installed runtime
+pub fn runtime_java_executable(runtime_dir: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") {
+        runtime_dir.join("jre.bundle/Contents/Home/bin/java")
+    } else if cfg!(windows) {
+        runtime_dir.join("bin").join("java.exe")
+    } else {
+        runtime_dir.join("bin").join("java")
+    }
+}
+
+impl FileManager {
+    /// Download and verify a Java runtime component, returning its java executable
+    pub async fn install_java_runtime(
+        &self,
+        component: &str,
+        minecraft_dir: &MinecraftDir,
+    ) -> Result<PathBuf> {
+        let platform = runtime_platform().ok_or_else(|| {
+            JavaError::not_found(format!(
+                "No managed Java runtime is available for {}/{}",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ))
+        })?;
+
+        debug!(
+            "Fetching Java runtime manifest from {}",
+            self.java_runtime_manifest_url
+        );
+        let index_bytes = self
+            .fetch_bytes(&self.java_runtime_manifest_url)
+            .await
+            .context("Failed to fetch Java runtime manifest")?;
+        let index: RuntimeIndex = serde_json::from_slice(&index_bytes)
+            .context("Failed to parse Java runtime manifest JSON")?;
+
+        let variant = index
+            .get(platform)
+            .and_then(|components| components.get(component))
+            .and_then(|variants| variants.first())
+            .ok_or_else(|| {
+                JavaError::not_found(format!(
+                    "Java runtime {component} is not available for {platform}"
+                ))
+            })?;
+
+        info!(
+            "Installing Java runtime {component} ({}) for {platform}",
+            variant.version.name
+        );
+
+        let manifest_bytes = self
+            .fetch_bytes(&variant.manifest.url)
+            .await
+            .with_context(|| format!("Failed to fetch file manifest for {component}"))?;
+        let actual_sha1 = sha1_hex(&manifest_bytes);
+        if actual_sha1 != variant.manifest.sha1 {
+            return Err(FileManagerError::validation_failed(format!(
+                "SHA1 mismatch for {component} manifest: expected {}, got {actual_sha1}",
+                variant.manifest.sha1
+            ))
+            .into());
+        }
+        let manifest: RuntimeManifest = serde_json::from_slice(&manifest_bytes)
+            .with_context(|| format!("Failed to parse file manifest for {component}"))?;
+
+        let runtime_dir = minecraft_dir.java_runtime_dir(component, platform);
+        self.install_runtime_files(&manifest, &runtime_dir)
+            .await
+            .with_context(|| format!("Failed to install Java runtime {component}"))?;
+
+        Ok(runtime_java_executable(&runtime_dir))
+    }
+
+    /// Lay out every directory, file and link of a runtime manifest under `runtime_dir`
+    async fn install_runtime_files(
+        &self,
+        manifest: &RuntimeManifest,
+        runtime_dir: &Path,
+    ) -> Result<()> {
+        // Validate every path up front so a bad manifest cannot write outside the runtime
+        let entries = manifest
+            .files
+            .iter()
+            .map(|(name, file)| {
+                let relative = safe_relative_path(name)?;
+                if let RuntimeFile::Link { target } = file {
+                    check_link_target(&relative, target)?;
+                }
+                Ok((relative, file))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        fs::create_dir_all(runtime_dir).await.with_context(|| {
+            format!(
+                "Failed to create runtime directory: {}",
+                runtime_dir.display()
+            )
+        })?;
+
+        for (relative, file) in &entries {
+            if matches!(file, RuntimeFile::Directory) {
+                reject_symlinked_components(runtime_dir, relative, true)?;
+                let path = runtime_dir.join(relative);
+                fs::create_dir_all(&path)
+                    .await
+                    .with_context(|| format!("Failed to create directory: {}", path.display()))?;
+            }
+        }
+
+        let files = entries
+            .iter()
+            .filter_map(|(relative, file)| match file {
+                RuntimeFile::File {
+                    executable,
+                    downloads,
+                } => Some(
+                    reject_symlinked_components(runtime_dir, relative, true)
+                        .map(|()| (runtime_dir.join(relative), *executable, &downloads.raw)),
+                ),
+                _ => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut downloaded = 0;
+        for batch in files.chunks(BATCH_SIZE) {
+            let results =
+                futures_util::future::join_all(batch.iter().map(|(path, executable, raw)| {
+                    self.install_runtime_file(path, *executable, raw)
+                }))
+                .await;
+
+            for result in results {
+                if result? {
+                    downloaded += 1;
+                }
+            }
+        }
+
+        for (relative, file) in &entries {
+            if let RuntimeFile::Link { target } = file {
+                reject_symlinked_components(runtime_dir, relative, false)?;
+                create_runtime_link(&runtime_dir.join(relative), target)?;
+            }
+        }
+
+        info!(
+            "✓ Java runtime installed: {downloaded} files downloaded, {} total",
+            files.len()
+        );
+        Ok(())
+    }
+
+    /// Download one runtime file unless a valid copy exists, returning whether it was downloaded
+    async fn install_runtime_file(
+        &self,
+        path: &Path,
+        executable: bool,
+        raw: &DownloadInfo,
+    ) -> Result<bool> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let downloaded = if self.is_file_valid(path, &raw.sha1).await? {
+            false
+        } else {
+            self.download_file_with_verification(&raw.url, path, &raw.sha1, raw.size)
+                .await
+                .with_context(|| format!("Failed to download {}", path.display()))?;
+            true
+        };
+
+        if executable {
+            set_executable(path)?;
+        }
+
+        Ok(downloaded)
+    }
+}
+
+/// Reject manifest paths that are absolute or climb out of the runtime directory
+fn safe_relative_path(name: &str) -> Result<PathBuf> {
+    let path = Path::new(name);
+    if path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path.to_path_buf())
+    } else {
+        Err(FileManagerError::validation_failed(format!(
+            "Refusing unsafe path in runtime manifest: {name}"
+        ))
+        .into())
+    }
+}
+
+/// Reject link targets that resolve outside the runtime directory
+///
+/// The target is resolved lexically from the directory holding the link, so
+/// `..` is allowed as long as it stays below the runtime root.
+fn check_link_target(name: &Path, target: &str) -> Result<()> {
+    let escapes = || {
+        FileManagerError::validation_failed(format!(
+            "Refusing link {} -> {target} in runtime manifest, it points outside the runtime",
+            name.display()
+        ))
+    };
+
+    let mut depth = name.parent().map_or(0, |parent| {
+        parent
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .count()
+    });
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(escapes)?,
+            Component::RootDir | Component::Prefix(_) => return Err(escapes().into()),
+        }
+    }
+    Ok(())
+}
+
+/// Refuse to write through a symlink inside the runtime directory
+///
+/// A link left by an earlier install or planted by another process could
+/// otherwise redirect the write outside the runtime. The last component is
+/// only checked when `including_last` is set, as links are replaced anyway.
+fn reject_symlinked_components(
+    runtime_dir: &Path,
+    relative: &Path,
+    including_last: bool,
+) -> Result<()> {
+    let components: Vec<_> = relative.components().collect();
+    let checked = if including_last {
+        components.len()
+    } else {
+        components.len().saturating_sub(1)
+    };
+
+    let mut path = runtime_dir.to_path_buf();
+    for component in &components[..checked] {
+        path.push(component);
+        if path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_symlink())
+        {
+            return Err(FileManagerError::validation_failed(format!(
+                "Refusing to write through symlink in runtime: {}",
+                path.display()
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_runtime_link(path: &Path, target: &str) -> Result<()> {
+    if path.symlink_metadata().is_ok() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to replace link: {}", path.display()))?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    std::os::unix::fs::symlink(target, path)
+        .with_context(|| format!("Failed to create link: {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn create_runtime_link(path: &Path, _target: &str) -> Result<()> {
+    // Runtimes for non-unix platforms do not ship links
+    debug!("Skipping runtime link {}", path.display());
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read permissions: {}", path.display()))?
+        .permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    std::fs::set_permissions(path, permissions)
+        .with_context(|| format!("Failed to mark executable: {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn sha1_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::launcher::JavaManager;
+    use crate::launcher::test_support;
+    use std::os::unix::fs::PermissionsExt;
+
+    const FAKE_JAVA: &str = "#!/bin/sh\necho 'openjdk version \"17.0.8\" 2023-07-18' >&2\n";
+
+    fn file_url(path: &Path) -> String {
+        url::Url::from_file_path(path).unwrap().to_string()
+    }
+
+    /// Write a runtime index, file manifest and payload for `java-runtime-gamma`
+    fn write_fixture(dir: &Path, extra_files: &serde_json::Value) -> String {
+        let java_rel = runtime_java_executable(Path::new(""));
+        let payload = dir.join("java.payload");
+        std::fs::write(&payload, FAKE_JAVA).unwrap();
+
+        let mut files = serde_json::json!({
+            "bin": { "type": "directory" },
+            java_rel.to_str().unwrap(): {
+                "type": "file",
+                "executable": true,
+                "downloads": { "raw": {
+                    "sha1": sha1_hex(FAKE_JAVA.as_bytes()),
+                    "size": FAKE_JAVA.len(),
+                    "url": file_url(&payload),
+                }},
+            },
+            "bin/java-link": { "type": "link", "target": "java" },
+        });
+        files
+            .as_object_mut()
+            .unwrap()
+            .extend(extra_files.as_object().unwrap().clone());
+
+        let manifest = serde_json::json!({ "files": files }).to_string();
+        let manifest_path = dir.join("manifest.json");
+        std::fs::write(&manifest_path, &manifest).unwrap();
+
+        let index = serde_json::json!({
+            runtime_platform().unwrap(): {
+                "java-runtime-gamma": [{
+                    "manifest": {
+                        "sha1": sha1_hex(manifest.as_bytes()),
+                        "size": manifest.len(),
+                        "url": file_url(&manifest_path),
+                    },
+                    "version": { "name": "17.0.8", "released": "2023-07-18T00:00:00+00:00" },
+                }],
+            },
+        });
+        let index_path = dir.join("all.json");
+        std::fs::write(&index_path, index.to_string()).unwrap();
+        file_url(&index_path)
+    }
+
+    #[tokio::test]
+    async fn test_install_java_runtime_from_fixture() {
+        if runtime_platform().is_none() {
+            return;
+        }
+        let dir = test_support::temp_dir("runtime");
+        std::fs::create_dir_all(&dir).unwrap();
+        let index_url = write_fixture(&dir, &serde_json::json!({}));
+        let minecraft_dir = MinecraftDir {
+            base_path: dir.join("minecraft"),
+        };
+
+        let file_manager = FileManager::new().with_java_runtime_manifest_url(index_url);
+        let java = file_manager
+            .install_java_runtime("java-runtime-gamma", &minecraft_dir)
+            .await
+            .unwrap();
+
+        assert!(java.starts_with(minecraft_dir.runtime_dir()));
+        assert_ne!(
+            std::fs::metadata(&java).unwrap().permissions().mode() & 0o111,
+            0
+        );
+        let installation = JavaManager::probe_java_installation(&java).unwrap();
+        assert_eq!(installation.major_version, 17);
+
+        // Installing again is a no-op that keeps the runtime valid
+        file_manager
+            .install_java_runtime("java-runtime-gamma", &minecraft_dir)
+            .await
+            .unwrap();
+        if !cfg!(target_os = "macos") {
+            let link = java.with_file_name("java-link");
+            assert_eq!(std::fs::read_link(link).unwrap(), Path::new("java"));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_install_java_runtime_rejects_escaping_paths() {
+        if runtime_platform().is_none() {
+            return;
+        }
+        let dir = test_support::temp_dir("runtime");
+        std::fs::create_dir_all(&dir).unwrap();
+        let index_url = write_fixture(
+            &dir,
+            &serde_json::json!({ "../escape": { "type": "directory" } }),
+        );
+        let minecraft_dir = MinecraftDir {
+            base_path: dir.join("minecraft"),
+        };
+
+        let result = FileManager::new()
+            .with_java_runtime_manifest_url(index_url)
+            .install_java_runtime("java-runtime-gamma", &minecraft_dir)
+            .await;
+
+        assert!(result.is_err());
+        assert!(
+            !minecraft_dir
+                .runtime_dir()
+                .join("java-runtime-gamma")
+                .exists()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_install_java_runtime_rejects_escaping_links() {
+        if runtime_platform().is_none() {
+            return;
+        }
+        assert!(check_link_target(Path::new("lib/libjli.so"), "../bin/java").is_ok());
+        for target in ["../../outside", "/etc/passwd", "a/../../.."] {
+            assert!(
+                check_link_target(Path::new("bin/link"), target).is_err(),
+                "{target}"
+            );
+        }
+
+        // A symlinked directory from an earlier install is not written through
+        let dir = test_support::temp_dir("runtime");
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        let index_url = write_fixture(&dir, &serde_json::json!({}));
+        let minecraft_dir = MinecraftDir {
+            base_path: dir.join("minecraft"),
+        };
+        let runtime_dir =
+            minecraft_dir.java_runtime_dir("java-runtime-gamma", runtime_platform().unwrap());
+        std::fs::create_dir_all(&runtime_dir).unwrap();
+        std::os::unix::fs::symlink(&outside, runtime_dir.join("bin")).unwrap();
+
+        let result = FileManager::new()
+            .with_java_runtime_manifest_url(index_url)
+            .install_java_runtime("java-runtime-gamma", &minecraft_dir)
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
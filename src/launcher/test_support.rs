@@ -0,0 +1,38 @@
+//! Fixtures shared by the launcher's unit tests
+
+use std::path::PathBuf;
+
+use crate::launcher::minecraft_dir::MinecraftDir;
+use crate::launcher::version::VersionInfo;
+
+/// A unique path under the system temp directory, not yet created
+pub fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rustified-{name}-{}", uuid::Uuid::new_v4()))
+}
+
+/// A Minecraft directory at a fresh [`temp_dir`]
+pub fn minecraft_dir(name: &str) -> MinecraftDir {
+    MinecraftDir {
+        base_path: temp_dir(name),
+    }
+}
+
+/// A minimal release version, with `extra` fields replacing or adding to the defaults
+#[allow(clippy::unwrap_used)]
+pub fn version_info(extra: serde_json::Value) -> VersionInfo {
+    let mut base = serde_json::json!({
+        "id": "1.20.4",
+        "type": "release",
+        "time": "2023-12-07T12:56:20+00:00",
+        "releaseTime": "2023-12-07T12:56:20+00:00",
+        "downloads": { "client": { "sha1": "", "size": 0, "url": "" } },
+        "libraries": [],
+        "mainClass": "net.minecraft.client.main.Main",
+        "assetIndex": { "id": "12", "sha1": "", "size": 0, "totalSize": 0, "url": "" },
+        "assets": "12"
+    });
+    if let (Some(base), serde_json::Value::Object(fields)) = (base.as_object_mut(), extra) {
+        base.extend(fields);
+    }
+    serde_json::from_value(base).unwrap()
+}
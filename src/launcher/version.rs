@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error::Result;
+use crate::launcher::maven::MavenCoordinate;
+use crate::launcher::rules::{self, LaunchFeatures};
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct VersionManifest {
     pub latest: LatestVersions,
@@ -55,6 +59,10 @@ pub struct VersionInfo {
     pub assets: String,
     #[serde(rename = "javaVersion")]
     pub java_version: Option<JavaVersion>,
+    pub logging: Option<Logging>,
+    /// Version whose client jar is launched, when it is not this version's own
+    #[serde(default)]
+    pub jar: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -77,13 +85,20 @@ pub struct DownloadInfo {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Library {
     pub name: String,
+    #[serde(default)]
     pub downloads: LibraryDownloads,
+    /// Maven repository of a library without `downloads`, as in loader profiles
+    pub url: Option<String>,
+    /// Checksum of the artifact at `url`, which some loader profiles include
+    pub sha1: Option<String>,
+    /// Size of the artifact at `url` in bytes
+    pub size: Option<u64>,
     pub rules: Option<Vec<Rule>>,
     pub natives: Option<HashMap<String, String>>,
     pub extract: Option<ExtractRules>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct LibraryDownloads {
     pub artifact: Option<DownloadInfo>,
     pub classifiers: Option<HashMap<String, DownloadInfo>>,
@@ -93,6 +108,8 @@ pub struct LibraryDownloads {
 pub struct Rule {
     pub action: String,
     pub os: Option<OsRule>,
+    /// Launcher features the rule requires, e.g. `is_quick_play_multiplayer`
+    pub features: Option<HashMap<String, bool>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -130,6 +147,38 @@ pub enum ArgumentValueType {
     Multiple(Vec<String>),
 }
 
+impl VersionInfo {
+    /// Version id of the client jar to launch, honouring the `jar` override
+    pub fn client_jar_id(&self) -> &str {
+        self.jar.as_deref().unwrap_or(&self.id)
+    }
+}
+
+impl ArgumentValue {
+    /// The argument's values, in order, before variable substitution
+    pub fn values(&self) -> &[String] {
+        match self {
+            Self::Simple(value)
+            | Self::Conditional {
+                value: ArgumentValueType::Single(value),
+                ..
+            } => std::slice::from_ref(value),
+            Self::Conditional {
+                value: ArgumentValueType::Multiple(values),
+                ..
+            } => values,
+        }
+    }
+
+    /// Rules gating the argument, if any
+    pub fn rules(&self) -> Option<&[Rule]> {
+        match self {
+            Self::Simple(_) => None,
+            Self::Conditional { rules, .. } => Some(rules),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AssetIndex {
     pub id: String,
@@ -147,6 +196,31 @@ pub struct JavaVersion {
     pub major_version: u32,
 }
 
+/// Logging configuration shipped with a version
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Logging {
+    pub client: Option<LoggingConfig>,
+}
+
+/// A log4j2 configuration file and the JVM argument that applies it
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoggingConfig {
+    /// JVM argument with a `${path}` placeholder, e.g. `-Dlog4j.configurationFile=${path}`
+    pub argument: String,
+    pub file: LoggingFile,
+    /// Format of the config, `log4j2-xml` for every version so far
+    #[serde(rename = "type")]
+    pub config_type: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoggingFile {
+    pub id: String,
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AssetManifest {
     pub objects: HashMap<String, AssetObject>,
@@ -161,74 +235,63 @@ pub struct AssetObject {
 impl Library {
     /// Check if this library should be used on the current platform
     pub fn should_use(&self) -> bool {
-        if let Some(rules) = &self.rules {
-            let mut allowed = false; // Start with disallow by default if there are rules
-
-            for rule in rules {
-                if Library::rule_matches(rule) {
-                    allowed = rule.action == "allow";
-                }
-            }
-
-            allowed
-        } else {
-            true // No rules means it's allowed for all platforms
-        }
+        // No rules means it's allowed for all platforms
+        self.rules
+            .as_ref()
+            .is_none_or(|rules| rules::rules_allow(rules, LaunchFeatures::default()))
     }
 
-    /// Check if a rule matches the current platform
-    fn rule_matches(rule: &Rule) -> bool {
-        if let Some(os_rule) = &rule.os {
-            // Check OS name
-            if let Some(name) = &os_rule.name {
-                let current_os = match std::env::consts::OS {
-                    "windows" => "windows",
-                    "linux" => "linux",
-                    "macos" => "osx",
-                    _ => return false,
-                };
-
-                if name != current_os {
-                    return false;
-                }
-            }
-
-            // Check architecture
-            if let Some(arch) = &os_rule.arch {
-                let current_arch = match std::env::consts::ARCH {
-                    "x86_64" => "x86_64",
-                    "aarch64" => "arm64",
-                    _ => return false,
-                };
-
-                if arch != current_arch {
-                    return false;
-                }
-            }
-
-            true
-        } else {
-            true // No OS rule means it matches all platforms
-        }
-    }
     /// Get the native classifier for the current platform (legacy format)
     pub fn get_native_classifier(&self) -> Option<String> {
-        if let Some(natives) = &self.natives {
-            let os_name = match std::env::consts::OS {
-                "windows" => "windows",
-                "linux" => "linux",
-                "macos" => "osx",
-                _ => return None,
-            };
-
-            natives.get(os_name).cloned()
-        } else {
-            None
-        }
+        self.native_classifier_for(rules::current_os_name()?, rules::current_arch_bits())
+    }
+
+    /// Legacy native classifier for `os`, with `${arch}` replaced by `bits` (`32` or `64`)
+    pub fn native_classifier_for(&self, os: &str, bits: &str) -> Option<String> {
+        let classifier = self.natives.as_ref()?.get(os)?;
+        Some(classifier.replace("${arch}", bits))
     }
 
     /// Check if this is a native library (modern format)
     pub fn is_native_library(&self) -> bool {
         self.name.contains(":natives-")
     }
+
+    /// Parsed Maven coordinate of the library
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the library name is not a valid coordinate.
+    pub fn coordinate(&self) -> Result<MavenCoordinate> {
+        MavenCoordinate::parse(&self.name)
+    }
+
+    /// Whether the library has a main artifact for the classpath
+    pub fn has_artifact(&self) -> bool {
+        self.downloads.artifact.is_some() || self.url.is_some()
+    }
+
+    /// Where to download the main artifact from
+    ///
+    /// Libraries with only a repository `url` use their own `sha1` and
+    /// `size` when the profile lists them. Otherwise the download is returned
+    /// with an empty `sha1` and a `size` of 0, and the checksum has to come
+    /// from the repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL has to be built from an invalid library name.
+    pub fn artifact_download(&self) -> Result<Option<DownloadInfo>> {
+        if let Some(artifact) = &self.downloads.artifact {
+            return Ok(Some(artifact.clone()));
+        }
+        let Some(repository) = &self.url else {
+            return Ok(None);
+        };
+        Ok(Some(DownloadInfo {
+            sha1: self.sha1.clone().unwrap_or_default(),
+            size: self.size.unwrap_or_default(),
+            url: self.coordinate()?.url(repository),
+        }))
+    }
 }
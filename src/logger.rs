@@ -55,6 +55,21 @@ fn format_level(level: tracing::Level) -> String {
     }
 }
 
+/// Formats a line of game output the same way as the launcher's own log lines
+///
+/// `time` is the game's timestamp and `source` the thread (and logger) that
+/// produced the message.
+pub fn format_game_line(time: &str, level: tracing::Level, source: &str, message: &str) -> String {
+    format!(
+        "{}{time}{} {} {}[{source}]{} {message}",
+        colors::GRAY,
+        colors::RESET,
+        format_level(level),
+        colors::GRAY,
+        colors::RESET
+    )
+}
+
 impl<S, N> tracing_subscriber::fmt::FormatEvent<S, N> for CustomFormat
 where
     S: tracing::Subscriber + for<'a> LookupSpan<'a>,
@@ -85,7 +100,8 @@ where
 ///
 /// This function sets up a beautiful, colored logger with custom time formatting
 /// and consistent level alignment. It respects the `RUST_LOG` environment variable
-/// for filtering, falling back to "info" level.
+/// for filtering, falling back to "info" level. Logs go to stderr so that
+/// machine-readable output (e.g. `--json`) on stdout stays clean.
 ///
 /// # Examples
 ///
@@ -107,6 +123,7 @@ pub fn init() {
         .with_level(true)
         .with_ansi(true)
         .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
         .fmt_fields(tracing_subscriber::fmt::format::DefaultFields::new())
         .event_format(CustomFormat)
         .init();
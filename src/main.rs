@@ -4,6 +4,7 @@ mod auth;
 pub mod cli;
 pub mod commands;
 pub mod error;
+mod fs_utils;
 mod launcher;
 mod logger;
 
@@ -58,8 +59,46 @@ async fn main() -> crate::error::Result<()> {
         Commands::Launch {
             instance,
             skip_verification,
+            detach,
+            game_log_level,
+            server,
+            world,
+            width,
+            height,
+            fullscreen,
+            windowed,
+            dry_run,
+            export_script,
         } => {
-            commands::game::launch_game(&launcher, &instance, skip_verification).await?;
+            let quick_play = match (server, world) {
+                (Some(server), _) => Some(launcher::QuickPlay::Multiplayer(
+                    launcher::ServerConfig::parse(&server)?,
+                )),
+                (None, Some(world)) => Some(launcher::QuickPlay::Singleplayer(world)),
+                (None, None) => None,
+            };
+            let options = launcher::LaunchOptions {
+                mode: if dry_run || export_script.is_some() {
+                    launcher::LaunchMode::DryRun
+                } else if detach {
+                    launcher::LaunchMode::Detached
+                } else {
+                    launcher::LaunchMode::Attached
+                },
+                game_log_level: game_log_level.into(),
+                quick_play,
+                width,
+                height,
+                fullscreen: (fullscreen || windowed).then_some(fullscreen),
+            };
+            commands::game::launch_game(
+                &launcher,
+                &instance,
+                skip_verification,
+                &options,
+                export_script.as_deref(),
+            )
+            .await?;
         }
         Commands::Prepare { version } => {
             commands::game::prepare_game(&launcher, &version).await?;
@@ -71,7 +110,7 @@ async fn main() -> crate::error::Result<()> {
             commands::instance::handle_instance_command(&launcher, action).await?;
         }
         Commands::Java { action } => {
-            commands::java::handle_java_command(&launcher, action);
+            commands::java::handle_java_command(&launcher, action).await?;
         }
     }
 